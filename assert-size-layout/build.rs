@@ -0,0 +1,32 @@
+//! Records the target being built for, which keys the sizes in `layout.lock` files.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+/// The `cfg` options that tell targets with different layouts apart, in the order they are
+/// written to lockfiles.
+const TARGET_CFGS: &[&str] = &["target_arch", "target_vendor", "target_os", "target_env", "target_abi", "target_pointer_width"];
+
+fn main() {
+    let target = env::var("TARGET").expect("`TARGET` is set by Cargo");
+    let cfgs: Vec<String> = TARGET_CFGS
+        .iter()
+        .map(|name| {
+            let value = env::var(format!("CARGO_CFG_{}", name.to_uppercase())).unwrap_or_default();
+            format!("({:?}, {:?})", name, value)
+        })
+        .collect();
+    let out_dir = PathBuf::from(env::var_os("OUT_DIR").expect("`OUT_DIR` is set by Cargo"));
+    fs::write(
+        out_dir.join("target.rs"),
+        format!(
+            "/// The target triple being built for.\npub const TARGET: &str = {:?};\n\n\
+             /// The `cfg` values of the target being built for.\npub const TARGET_CFG: &[(&str, &str)] = &[{}];\n",
+            target,
+            cfgs.join(", ")
+        ),
+    )
+    .unwrap();
+    println!("cargo:rerun-if-changed=build.rs");
+}
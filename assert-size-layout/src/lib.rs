@@ -0,0 +1,116 @@
+//! Layout types used by the code generated by
+//! [`assert-size-derive`](https://docs.rs/assert-size-derive).
+//!
+//! Procedural macro crates can only export macros, so the types that generated code refers
+//! to live here. Depend on this crate alongside `assert-size-derive` when using the
+//! `report` option of `#[assert_size]`.
+//!
+//! ```
+//! use assert_size_derive::assert_size;
+//!
+//! #[assert_size(8, report)]
+//! #[repr(C)]
+//! struct Header {
+//!     kind: u8,
+//!     length: u32,
+//! }
+//!
+//! assert_eq!(Header::LAYOUT[0].padding_after, 3);
+//! ```
+//!
+//! With the default `std` feature, the crate also checks the layout snapshots written by
+//! the tests that the `test` option generates.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::fmt;
+
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub mod lockfile;
+#[cfg(feature = "std")]
+mod snapshot;
+
+#[cfg(feature = "std")]
+pub use snapshot::check_snapshot;
+
+/// The layout of a type, as computed for the current target.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TypeLayout<'a> {
+    /// The name of the type, including any generic arguments.
+    pub name: &'a str,
+    /// The size of the type, in bytes.
+    pub size: usize,
+    /// The alignment of the type, in bytes.
+    pub align: usize,
+    /// The layout of each field in declaration order, or none for enums.
+    pub fields: &'a [FieldLayout],
+}
+
+impl fmt::Display for TypeLayout<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.name)?;
+        writeln!(f, "  size: {}", self.size)?;
+        writeln!(f, "  align: {}", self.align)?;
+        for field in self.fields {
+            writeln!(
+                f,
+                "  {}: offset {}, size {}, align {}, padding {}",
+                field.name, field.offset, field.size, field.align, field.padding_after
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The layout of a single field of a type, as computed for the current target.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FieldLayout {
+    /// The name of the field, or its index for tuple-struct fields.
+    pub name: &'static str,
+    /// The offset of the field from the start of the type, in bytes.
+    pub offset: usize,
+    /// The size of the field, in bytes.
+    pub size: usize,
+    /// The alignment of the field, in bytes.
+    pub align: usize,
+    /// The number of padding bytes between the end of the field and the next field in
+    /// memory, or the end of the type for the last field.
+    pub padding_after: usize,
+}
+
+impl FieldLayout {
+    /// Creates the layout of a field whose padding is not yet known.
+    pub const fn new(name: &'static str, offset: usize, size: usize, align: usize) -> Self {
+        FieldLayout { name, offset, size, align, padding_after: 0 }
+    }
+
+    /// Creates the layout of a field from a projection of a pointer to the type onto the
+    /// field, from which the type of the field is inferred. This allows the layout to be
+    /// computed for an instantiation of a generic type without naming the field's type.
+    #[doc(hidden)]
+    pub const fn of_field<T, F>(name: &'static str, offset: usize, _project: fn(*const T) -> *const F) -> Self {
+        FieldLayout::new(name, offset, core::mem::size_of::<F>(), core::mem::align_of::<F>())
+    }
+
+    /// Fills in the padding following each of `fields`, which make up a type of
+    /// `type_size` bytes. Fields may be given in any order, since the compiler is free to
+    /// reorder them in memory.
+    pub const fn with_padding<const N: usize>(mut fields: [FieldLayout; N], type_size: usize) -> [FieldLayout; N] {
+        let mut i = 0;
+        while i < N {
+            let end = fields[i].offset + fields[i].size;
+            let mut next = type_size;
+            let mut j = 0;
+            while j < N {
+                if j != i && fields[j].offset >= end && fields[j].offset < next {
+                    next = fields[j].offset;
+                }
+                j += 1;
+            }
+            fields[i].padding_after = next - end;
+            i += 1;
+        }
+        fields
+    }
+}
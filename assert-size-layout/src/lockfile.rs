@@ -0,0 +1,160 @@
+//! Reading and writing of `layout.lock` files, which record the sizes of the types using
+//! the `snapshot` option of `#[assert_size]`.
+//!
+//! The file is a small subset of TOML, with one table per target triple holding the `cfg`
+//! values that select the target, and the size of each type, keyed by its module path and
+//! name:
+//!
+//! ```toml
+//! ["x86_64-unknown-linux-gnu"]
+//! cfg = { target_arch = "x86_64", target_vendor = "unknown", target_os = "linux", target_env = "gnu", target_abi = "", target_pointer_width = "64" }
+//! "my_crate::net::Header" = 16
+//! "my_crate::net::Wrapper<u64>" = 8
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io::ErrorKind;
+use std::sync::Mutex;
+
+/// The environment variable that makes the tests generated by the `snapshot` option
+/// record the sizes of their types instead of leaving the lockfile alone.
+pub const BLESS_VAR: &str = "ASSERT_SIZE_BLESS";
+
+include!(concat!(env!("OUT_DIR"), "/target.rs"));
+
+/// The sizes recorded in a lockfile, by target triple.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Lockfile {
+    pub targets: BTreeMap<String, TargetSizes>,
+}
+
+/// The sizes recorded for one target.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TargetSizes {
+    /// The `cfg` options and values that select the target, such as `("target_os", "linux")`.
+    pub cfg: Vec<(String, String)>,
+    /// The size of each type, by module path and name.
+    pub sizes: BTreeMap<String, usize>,
+}
+
+impl Lockfile {
+    /// Parses the contents of a lockfile, returning an error naming the offending line.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lockfile = Lockfile::default();
+        let mut target: Option<String> = None;
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: &str| format!("line {}: {}", index + 1, message);
+
+            if let Some(header) = line.strip_prefix('[') {
+                let name = header
+                    .strip_suffix(']')
+                    .and_then(|name| unquote(name.trim()))
+                    .ok_or_else(|| error("expected a quoted target such as `[\"x86_64-unknown-linux-gnu\"]`"))?;
+                lockfile.targets.entry(name.to_owned()).or_default();
+                target = Some(name.to_owned());
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| error("expected an entry such as `\"my_crate::Header\" = 16`"))?;
+            let target = target.as_ref().ok_or_else(|| error("expected a target before the first entry"))?;
+            let recorded = lockfile.targets.get_mut(target).unwrap();
+            if key.trim() == "cfg" {
+                recorded.cfg = parse_cfg(value.trim())
+                    .ok_or_else(|| error("expected `cfg = { target_os = \"...\", ... }`"))?;
+                continue;
+            }
+            let key = unquote(key.trim()).ok_or_else(|| error("expected a quoted type name"))?;
+            let size = value.trim().parse().map_err(|_| error("expected a size in bytes"))?;
+            recorded.sizes.insert(key.to_owned(), size);
+        }
+        Ok(lockfile)
+    }
+}
+
+impl fmt::Display for Lockfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# Type sizes checked by `#[assert_size(snapshot)]`.")?;
+        writeln!(f, "# Regenerate with `{}=1 cargo test`.", BLESS_VAR)?;
+        for (target, recorded) in &self.targets {
+            writeln!(f)?;
+            writeln!(f, "[\"{}\"]", target)?;
+            let cfg: Vec<String> = recorded.cfg.iter().map(|(name, value)| format!("{} = \"{}\"", name, value)).collect();
+            writeln!(f, "cfg = {{ {} }}", cfg.join(", "))?;
+            for (name, size) in &recorded.sizes {
+                writeln!(f, "\"{}\" = {}", name, size)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses an inline table of `cfg` values such as `{ target_os = "linux" }`.
+fn parse_cfg(text: &str) -> Option<Vec<(String, String)>> {
+    let inner = text.strip_prefix('{')?.strip_suffix('}')?.trim();
+    if inner.is_empty() {
+        return Some(Vec::new());
+    }
+    inner
+        .split(',')
+        .map(|entry| {
+            let (name, value) = entry.split_once('=')?;
+            let name = name.trim();
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                return None;
+            }
+            Some((name.to_owned(), unquote(value.trim())?.to_owned()))
+        })
+        .collect()
+}
+
+/// Strips the double quotes around a string without escapes.
+fn unquote(text: &str) -> Option<&str> {
+    text.strip_prefix('"')?.strip_suffix('"').filter(|inner| !inner.contains(['"', '\\']))
+}
+
+/// Whether the [`BLESS_VAR`] environment variable is set to record sizes.
+pub fn blessing() -> bool {
+    std::env::var_os(BLESS_VAR).is_some_and(|bless| !bless.is_empty() && bless != "0")
+}
+
+/// The key of the current target in a lockfile, which is its triple as in
+/// `x86_64-unknown-linux-gnu`.
+pub fn current_target() -> &'static str {
+    TARGET
+}
+
+/// Serializes the tests that update the same lockfile from different threads.
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// Records the `sizes` of the types named in `module` in the lockfile at `path` for the
+/// current target, if the [`BLESS_VAR`] environment variable is set. Entries of types that
+/// no longer exist are kept, so the lockfile should be deleted before blessing to remove
+/// them.
+#[track_caller]
+pub fn bless_sizes(path: &str, module: &str, sizes: &[(&str, usize)]) {
+    if !blessing() {
+        return;
+    }
+
+    let _guard = LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut lockfile = match fs::read_to_string(path) {
+        Ok(text) => Lockfile::parse(&text).unwrap_or_else(|err| panic!("failed to parse `{}`: {}", path, err)),
+        Err(err) if err.kind() == ErrorKind::NotFound => Lockfile::default(),
+        Err(err) => panic!("failed to read `{}`: {}", path, err),
+    };
+
+    let recorded = lockfile.targets.entry(current_target().to_owned()).or_default();
+    recorded.cfg = TARGET_CFG.iter().map(|&(name, value)| (name.to_owned(), value.to_owned())).collect();
+    for &(name, size) in sizes {
+        recorded.sizes.insert(format!("{}::{}", module, name), size);
+    }
+    fs::write(path, lockfile.to_string()).unwrap_or_else(|err| panic!("failed to write `{}`: {}", path, err));
+}
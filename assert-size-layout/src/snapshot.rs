@@ -0,0 +1,93 @@
+//! Comparison of layouts against snapshot files.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use crate::TypeLayout;
+use crate::lockfile::{BLESS_VAR, blessing};
+
+/// Checks `layouts` against the snapshot of the type `name` in the directory `dir`,
+/// panicking with a line diff if they differ, or if there is no snapshot. With the
+/// [`BLESS_VAR`] environment variable set, the snapshot is written instead, recording the
+/// current layouts.
+///
+/// `name` is the path of the type, such as `my_crate::net::Header`, and the snapshot file
+/// is named after it with `::` replaced by `__`.
+#[track_caller]
+pub fn check_snapshot(dir: &str, name: &str, layouts: &[TypeLayout<'_>]) {
+    let actual: String = layouts
+        .iter()
+        .map(|layout| layout.to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let path = Path::new(dir).join(format!("{}.snap", name.replace("::", "__")));
+
+    // Snapshots may have been checked out with Windows line endings.
+    let expected = match fs::read_to_string(&path) {
+        Ok(expected) => Some(expected.replace("\r\n", "\n")),
+        Err(err) if err.kind() == ErrorKind::NotFound => None,
+        Err(err) => panic!("failed to read `{}`: {}", path.display(), err),
+    };
+    if expected.as_ref() == Some(&actual) {
+        return;
+    }
+
+    if blessing() {
+        fs::create_dir_all(dir).unwrap_or_else(|err| panic!("failed to create `{}`: {}", dir, err));
+        fs::write(&path, &actual).unwrap_or_else(|err| panic!("failed to write `{}`: {}", path.display(), err));
+        return;
+    }
+    match expected {
+        Some(expected) => panic!(
+            "the layout of `{}` does not match the snapshot in `{}`; run `{}=1 cargo test` to record the new layout\n\n{}",
+            name,
+            path.display(),
+            BLESS_VAR,
+            diff(&expected, &actual)
+        ),
+        None => panic!(
+            "there is no snapshot of the layout of `{}` in `{}`; run `{}=1 cargo test` to record it",
+            name,
+            path.display(),
+            BLESS_VAR
+        ),
+    }
+}
+
+/// Diffs the lines of `expected` and `actual`, prefixing removed lines with `-`, added
+/// lines with `+` and unchanged lines with a space.
+fn diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+
+    // The length of the longest common subsequence of the remaining lines, from each pair
+    // of starting positions.
+    let mut common = vec![vec![0; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            common[i][j] = if expected[i] == actual[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut printed = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            printed.push_str(&format!(" {}\n", expected[i]));
+            i += 1;
+            j += 1;
+        } else if i < expected.len() && (j == actual.len() || common[i + 1][j] >= common[i][j + 1]) {
+            printed.push_str(&format!("-{}\n", expected[i]));
+            i += 1;
+        } else {
+            printed.push_str(&format!("+{}\n", actual[j]));
+            j += 1;
+        }
+    }
+    printed
+}
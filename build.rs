@@ -0,0 +1,4 @@
+// Gives the tests an `OUT_DIR` to write the headers of `c_export` to.
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+}
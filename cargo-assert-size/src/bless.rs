@@ -0,0 +1,209 @@
+//! `cargo assert-size bless`: rewrites the expected sizes of failing assertions to the
+//! actual ones.
+//!
+//! The crate is checked with `cargo check`, and each failed size assertion is read from
+//! its diagnostic, whose span is the expected value to rewrite. Alignments and offsets are
+//! left alone, since a change in them is rarely intended.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::check::{self, Check, Mismatch};
+
+/// How many times the crate is blessed at most. Assertions in a crate that fails to
+/// compile hide those in the crates depending on it, so each pass may find new ones.
+const MAX_PASSES: usize = 8;
+
+/// A rewrite of an expected value in a source file.
+struct Edit {
+    start: usize,
+    end: usize,
+    replacement: String,
+    /// The line of the expected value, which blessing does not change.
+    line: usize,
+    actual: u128,
+}
+
+/// Rewrites the failing expected sizes under the crate or workspace of `manifest`, or only
+/// prints the changes as a diff with `dry_run`. Fails if any assertion is left failing,
+/// such as one whose expected value is not a literal or whose actual size differs between
+/// targets.
+pub fn bless(manifest: Option<&Path>, dry_run: bool) -> Result<(), String> {
+    let root = check::metadata(manifest)?.workspace_root;
+    // The sizes blessed so far, by file and line, to catch a size that a target checked in
+    // a later pass disagrees with.
+    let mut blessed: BTreeMap<(PathBuf, usize), u128> = BTreeMap::new();
+    for pass in 0..=MAX_PASSES {
+        let Check { mismatches, failure } = check::check(manifest, &root, None)?;
+        if mismatches.is_empty() && failure.is_none() {
+            if blessed.is_empty() {
+                eprintln!("nothing to bless");
+            }
+            return Ok(());
+        }
+        let Edits { mut edits, mut unblessable } = edits(&mismatches, &root)?;
+        for (file, file_edits) in &mut edits {
+            file_edits.retain(|edit| match blessed.get(&(file.clone(), edit.line)) {
+                Some(&size) if size != edit.actual => {
+                    let location = file.strip_prefix(&root).unwrap_or(file).display();
+                    unblessable.push(format!(
+                        "the expected size at {}:{} is {} or {} bytes depending on the target, so it cannot be blessed",
+                        location, edit.line, size, edit.actual
+                    ));
+                    false
+                }
+                _ => true,
+            });
+        }
+        edits.retain(|_, file_edits| !file_edits.is_empty());
+        if dry_run {
+            bless_files(&edits, &root, true)?;
+            return finish(&unblessable, failure.filter(|_| edits.is_empty()));
+        }
+        if edits.is_empty() {
+            return finish(&unblessable, failure);
+        }
+        if pass == MAX_PASSES {
+            break;
+        }
+        bless_files(&edits, &root, false)?;
+        for (file, file_edits) in &edits {
+            blessed.extend(file_edits.iter().map(|edit| ((file.clone(), edit.line), edit.actual)));
+        }
+    }
+    Err(format!("assertions still fail after blessing {} times", MAX_PASSES))
+}
+
+/// Reports what is left failing once nothing more can be blessed: the assertions that
+/// cannot be, and the output of `cargo check` if it fails for another reason.
+fn finish(unblessable: &[String], failure: Option<String>) -> Result<(), String> {
+    for reason in unblessable {
+        eprintln!("warning: {}", reason);
+    }
+    if let Some(stderr) = &failure {
+        eprint!("{}", stderr);
+    }
+    match (unblessable.len(), failure) {
+        (0, None) => Ok(()),
+        (0, Some(_)) => Err("`cargo check` fails, but no more expected sizes can be blessed".to_owned()),
+        (count, _) => Err(format!("{} expected size(s) must be updated by hand", count)),
+    }
+}
+
+/// Applies `edits` to their files, or only prints them as a diff with `dry_run`.
+fn bless_files(edits: &BTreeMap<PathBuf, Vec<Edit>>, root: &Path, dry_run: bool) -> Result<(), String> {
+    for (file, edits) in edits {
+        let text = fs::read_to_string(file).map_err(|err| format!("failed to read `{}`: {}", file.display(), err))?;
+        let blessed_text = apply(&text, edits);
+        let relative = file.strip_prefix(root).unwrap_or(file);
+        if dry_run {
+            print!("{}", diff(relative, &text, &blessed_text));
+        } else {
+            fs::write(file, blessed_text).map_err(|err| format!("failed to write `{}`: {}", file.display(), err))?;
+            eprintln!("blessed {} expected size(s) in `{}`", edits.len(), relative.display());
+        }
+    }
+    Ok(())
+}
+
+/// The edits blessing a set of mismatches, and why the others cannot be blessed.
+struct Edits {
+    edits: BTreeMap<PathBuf, Vec<Edit>>,
+    unblessable: Vec<String>,
+}
+
+/// The edits blessing the literals of `mismatches`, by file. An expected value that fails
+/// in several targets, such as a library and its unit tests, is only rewritten if its
+/// actual size is the same in all of them.
+fn edits(mismatches: &[Mismatch], root: &Path) -> Result<Edits, String> {
+    let mut by_span: BTreeMap<(&Path, usize, usize), Vec<&Mismatch>> = BTreeMap::new();
+    for mismatch in mismatches {
+        by_span.entry((&mismatch.file, mismatch.start, mismatch.end)).or_default().push(mismatch);
+    }
+
+    let mut edits: BTreeMap<PathBuf, Vec<Edit>> = BTreeMap::new();
+    let mut unblessable = Vec::new();
+    for ((file, start, end), mismatches) in by_span {
+        let actuals: BTreeSet<u128> = mismatches.iter().map(|mismatch| mismatch.actual).collect();
+        let text = fs::read_to_string(file).map_err(|err| format!("failed to read `{}`: {}", file.display(), err))?;
+        let Some(literal) = text.get(start..end) else {
+            continue;
+        };
+        let line = text[..start].matches('\n').count() + 1;
+        let location = format!("{}:{}", file.strip_prefix(root).unwrap_or(file).display(), line);
+        if actuals.len() > 1 {
+            let actuals: Vec<String> = actuals.iter().map(u128::to_string).collect();
+            unblessable.push(format!(
+                "the expected size at {} is {} bytes depending on the target, so it cannot be blessed",
+                location,
+                actuals.join(" or ")
+            ));
+            continue;
+        }
+        // Expected values given as expressions such as `HEADER_LEN + 4` are left to be
+        // updated by hand.
+        let actual = *actuals.first().unwrap();
+        let Some(replacement) = rewrite_literal(literal, mismatches[0].expected, actual) else {
+            unblessable.push(format!("the expected size at {} is `{}`, which is not an integer literal", location, literal));
+            continue;
+        };
+        edits.entry(file.to_path_buf()).or_default().push(Edit { start, end, replacement, line, actual });
+    }
+    Ok(Edits { edits, unblessable })
+}
+
+/// Rewrites the integer literal `literal`, which must have the value `expected` if it is
+/// known, to have the value `actual`, keeping its radix prefix and type suffix.
+fn rewrite_literal(literal: &str, expected: Option<u128>, actual: u128) -> Option<String> {
+    let (prefix, radix) = match literal.get(..2) {
+        Some("0x") => ("0x", 16),
+        Some("0o") => ("0o", 8),
+        Some("0b") => ("0b", 2),
+        _ => ("", 10),
+    };
+    let body = &literal[prefix.len()..];
+    let digits_len = body.find(|c: char| !(c.is_digit(radix) || c == '_')).unwrap_or(body.len());
+    let (digits, suffix) = body.split_at(digits_len);
+    let suffix_is_type = suffix.is_empty()
+        || ["u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize"].contains(&suffix);
+    let value = u128::from_str_radix(&digits.replace('_', ""), radix).ok()?;
+    if digits.is_empty() || !suffix_is_type || expected.is_some_and(|expected| value != expected) {
+        return None;
+    }
+
+    let digits = match radix {
+        16 => format!("{:x}", actual),
+        8 => format!("{:o}", actual),
+        2 => format!("{:b}", actual),
+        _ => actual.to_string(),
+    };
+    Some(format!("{}{}{}", prefix, digits, suffix))
+}
+
+/// Applies `edits`, which do not overlap, to `text`.
+fn apply(text: &str, edits: &[Edit]) -> String {
+    let mut edits: Vec<&Edit> = edits.iter().collect();
+    edits.sort_by_key(|edit| edit.start);
+    let mut blessed = String::with_capacity(text.len());
+    let mut copied = 0;
+    for edit in edits {
+        blessed += &text[copied..edit.start];
+        blessed += &edit.replacement;
+        copied = edit.end;
+    }
+    blessed += &text[copied..];
+    blessed
+}
+
+/// A diff of the lines that differ between `before` and `after`. Blessing only rewrites
+/// literals, so the lines correspond one to one.
+fn diff(file: &Path, before: &str, after: &str) -> String {
+    let mut diff = format!("--- {}\n+++ {}\n", file.display(), file.display());
+    for (index, (old, new)) in before.lines().zip(after.lines()).enumerate() {
+        if old != new {
+            diff += &format!("@@ -{} +{} @@\n-{}\n+{}\n", index + 1, index + 1, old, new);
+        }
+    }
+    diff
+}
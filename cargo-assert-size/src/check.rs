@@ -0,0 +1,141 @@
+//! Running `cargo check` and reading the failed size assertions from its JSON diagnostics.
+//!
+//! A size mismatch is reported on the expected value with the message `size of `T` is A
+//! bytes, but E bytes were expected` and the label `expected E bytes, found A bytes`, or,
+//! with `level = "warn"`, in a warning about an unused result of a type such as
+//! `SizeOf<T, Expected<E>, Found<A>>`. Either way, the span of the diagnostic is the
+//! expected value as written. Failed alignments and offsets are not read.
+//!
+//! The compiler gives expected values by name rather than by value when they are
+//! `usize::MAX`, which then cannot be read.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use serde_json::Value;
+
+/// A failed size assertion read from a diagnostic.
+pub struct Mismatch {
+    /// The file of the expected value, which is absolute.
+    pub file: PathBuf,
+    /// The byte range of the expected value in `file`.
+    pub start: usize,
+    pub end: usize,
+    /// The expected size, or `None` if it is `usize::MAX`.
+    pub expected: Option<u128>,
+    pub actual: u128,
+}
+
+/// The outcome of checking the crate once.
+pub struct Check {
+    /// The failed size assertions, in the order they were reported. The same assertion
+    /// fails once per target that compiles it, such as a library and its unit tests.
+    pub mismatches: Vec<Mismatch>,
+    /// The output of Cargo, if the check failed.
+    pub failure: Option<String>,
+}
+
+/// The directories of a workspace, as given by `cargo metadata`.
+pub struct Metadata {
+    /// The root directory of the workspace, which the file names in diagnostics are
+    /// relative to.
+    pub workspace_root: PathBuf,
+    /// The directory of the build output.
+    pub target_directory: PathBuf,
+}
+
+/// Reads the directories of the crate or workspace of `manifest`.
+pub fn metadata(manifest: Option<&Path>) -> Result<Metadata, String> {
+    let mut command = cargo();
+    command.args(["metadata", "--format-version", "1", "--no-deps"]);
+    if let Some(manifest) = manifest {
+        command.arg("--manifest-path").arg(manifest);
+    }
+    let output = command.stderr(Stdio::inherit()).output().map_err(|err| format!("failed to run cargo: {}", err))?;
+    if !output.status.success() {
+        return Err("`cargo metadata` failed".to_owned());
+    }
+    let metadata: Value = serde_json::from_slice(&output.stdout)
+        .map_err(|err| format!("failed to parse the output of `cargo metadata`: {}", err))?;
+    let directory = |key: &str| {
+        metadata[key].as_str().map(PathBuf::from).ok_or_else(|| format!("`cargo metadata` did not print the `{}`", key))
+    };
+    Ok(Metadata { workspace_root: directory("workspace_root")?, target_directory: directory("target_directory")? })
+}
+
+/// Checks every target of the crate or workspace of `manifest`, whose root is `root`,
+/// building in `target_dir` if given. Warnings are only emitted once there are no errors,
+/// so assertions with `level = "warn"` are only found once all others hold.
+pub fn check(manifest: Option<&Path>, root: &Path, target_dir: Option<&Path>) -> Result<Check, String> {
+    let mut command = cargo();
+    // Without `--keep-going`, a library and its unit tests, which may disagree on a size,
+    // are not always both checked.
+    command.args(["check", "--workspace", "--all-targets", "--keep-going", "--message-format=json"]);
+    if let Some(manifest) = manifest {
+        command.arg("--manifest-path").arg(manifest);
+    }
+    if let Some(target_dir) = target_dir {
+        command.arg("--target-dir").arg(target_dir);
+    }
+    let output = command.output().map_err(|err| format!("failed to run cargo: {}", err))?;
+
+    let mut mismatches = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let Ok(message) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        if message["reason"] != "compiler-message" {
+            continue;
+        }
+        let diagnostic = &message["message"];
+        let Some(span) = diagnostic["spans"]
+            .as_array()
+            .and_then(|spans| spans.iter().find(|span| span["is_primary"] == true))
+        else {
+            continue;
+        };
+        let Some((expected, actual)) = mismatch(diagnostic, span) else {
+            continue;
+        };
+        if let (Some(file), Some(start), Some(end)) =
+            (span["file_name"].as_str(), span["byte_start"].as_u64(), span["byte_end"].as_u64())
+        {
+            mismatches.push(Mismatch { file: root.join(file), start: start as usize, end: end as usize, expected, actual });
+        }
+    }
+    let failure = (!output.status.success()).then(|| String::from_utf8_lossy(&output.stderr).into_owned());
+    Ok(Check { mismatches, failure })
+}
+
+/// The expected and actual sizes of a failed size assertion, if the diagnostic is one.
+fn mismatch(diagnostic: &Value, span: &Value) -> Option<(Option<u128>, u128)> {
+    let message = diagnostic["message"].as_str()?;
+    if let Some(label) = span["label"].as_str() {
+        if !(message.starts_with("size of `") && message.ends_with(" were expected")) {
+            return None;
+        }
+        let (expected, actual) = label.strip_prefix("expected ")?.strip_suffix(" bytes")?.split_once(" bytes, found ")?;
+        return Some((expected_size(expected)?, actual.parse().ok()?));
+    }
+    message.strip_prefix("unused result of type `SizeOf<")?;
+    let value_after = |marker: &str| -> Option<&str> {
+        let rest = &message[message.find(marker)? + marker.len()..];
+        Some(&rest[..rest.find('>')?])
+    };
+    Some((expected_size(value_after("Expected<")?)?, value_after("Found<")?.parse().ok()?))
+}
+
+/// Reads an expected size as the compiler prints it, which is `Some(None)` for
+/// `usize::MAX`.
+fn expected_size(text: &str) -> Option<Option<u128>> {
+    match text {
+        "usize::MAX" => Some(None),
+        _ => text.parse().ok().map(Some),
+    }
+}
+
+/// The Cargo that invoked the subcommand, or the one on the `PATH`.
+fn cargo() -> Command {
+    Command::new(env::var_os("CARGO").unwrap_or_else(|| "cargo".into()))
+}
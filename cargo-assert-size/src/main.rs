@@ -0,0 +1,200 @@
+//! `cargo assert-size`: lists the types checked by `assert-size-derive` in a crate or
+//! workspace, along with what they assert, blesses their expected sizes, and suggests
+//! assertions for the types that have none.
+//!
+//! ```text
+//! cargo assert-size [list] [--json] [--manifest-path <path>]
+//! cargo assert-size bless [--dry-run] [--manifest-path <path>]
+//! cargo assert-size suggest [--write] [--module <path>]... [--repr <hint>]...
+//!                           [--visibility <pub|crate|all>] [--manifest-path <path>]
+//! ```
+
+mod bless;
+mod check;
+mod scan;
+mod suggest;
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use scan::Assertion;
+use serde_json::json;
+
+const USAGE: &str = "\
+usage: cargo assert-size [list] [--json] [--manifest-path <path>]
+       cargo assert-size bless [--dry-run] [--manifest-path <path>]
+       cargo assert-size suggest [--write] [--module <path>]... [--repr <hint>]...
+                                 [--visibility <pub|crate|all>] [--manifest-path <path>]";
+
+enum Subcommand {
+    /// Lists the assertions, as JSON with `--json`.
+    List { json: bool },
+    /// Rewrites the failing expected sizes, or prints the changes with `--dry-run`.
+    Bless { dry_run: bool },
+    /// Proposes annotations for the unasserted types matching the filters, or inserts
+    /// them with `--write`.
+    Suggest { filters: suggest::Filters, write: bool },
+}
+
+struct Options {
+    subcommand: Subcommand,
+    /// The manifest given with `--manifest-path`, if any.
+    manifest: Option<PathBuf>,
+    /// The directory whose sources are scanned.
+    root: PathBuf,
+}
+
+fn main() -> ExitCode {
+    match parse_options(env::args().skip(1)).and_then(|options| run(&options)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_options(args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut args = args.peekable();
+    // Cargo passes the name of the subcommand as the first argument.
+    args.next_if(|arg| arg == "assert-size");
+    let name = args.next_if(|arg| ["list", "bless", "suggest"].contains(&arg.as_str())).unwrap_or_default();
+
+    let mut manifest = None;
+    let mut json = false;
+    let mut dry_run = false;
+    let mut write = false;
+    let mut filters = suggest::Filters { modules: Vec::new(), reprs: Vec::new(), visibility: suggest::Visibility::Pub };
+    while let Some(arg) = args.next() {
+        match (name.as_str(), arg.as_str()) {
+            ("" | "list", "--json") => json = true,
+            ("bless", "--dry-run") => dry_run = true,
+            ("suggest", "--write") => write = true,
+            ("suggest", "--module") => {
+                let module = args.next().ok_or("`--module` requires a module path")?;
+                // Paths are relative to the crate root, with or without `crate::`.
+                let segments = module.split("::").skip_while(|segment| *segment == "crate");
+                filters.modules.push(segments.filter(|segment| !segment.is_empty()).map(str::to_owned).collect());
+            }
+            ("suggest", "--repr") => filters.reprs.push(args.next().ok_or("`--repr` requires a representation hint")?),
+            ("suggest", "--visibility") => {
+                filters.visibility = match args.next().as_deref() {
+                    Some("pub") => suggest::Visibility::Pub,
+                    Some("crate") => suggest::Visibility::Crate,
+                    Some("all") => suggest::Visibility::All,
+                    _ => return Err("`--visibility` requires one of `pub`, `crate` or `all`".to_owned()),
+                };
+            }
+            (_, "--manifest-path") => {
+                let path = PathBuf::from(args.next().ok_or("`--manifest-path` requires a path")?);
+                if !path.is_file() {
+                    return Err(format!("manifest path `{}` does not exist", path.display()));
+                }
+                manifest = Some(path);
+            }
+            (_, "-h" | "--help") => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            _ => return Err(format!("unexpected argument `{}`\n{}", arg, USAGE)),
+        }
+    }
+
+    let root = match manifest.as_deref().map(|manifest| manifest.parent().unwrap_or(Path::new(""))) {
+        Some(root) if root.as_os_str().is_empty() => PathBuf::from("."),
+        Some(root) => root.to_path_buf(),
+        None => env::current_dir().map_err(|err| format!("failed to read the current directory: {}", err))?,
+    };
+    let subcommand = match name.as_str() {
+        "bless" => Subcommand::Bless { dry_run },
+        "suggest" => Subcommand::Suggest { filters, write },
+        _ => Subcommand::List { json },
+    };
+    Ok(Options { subcommand, manifest, root })
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let json = match &options.subcommand {
+        Subcommand::List { json } => *json,
+        Subcommand::Bless { dry_run } => return bless::bless(options.manifest.as_deref(), *dry_run),
+        Subcommand::Suggest { filters, write } => {
+            return suggest::suggest(options.manifest.as_deref(), &options.root, filters, *write);
+        }
+    };
+
+    let root = &options.root;
+    let files = scan::find_source_files(root).map_err(|err| format!("failed to read `{}`: {}", root.display(), err))?;
+    let mut assertions = Vec::new();
+    for file in files {
+        let text = fs::read_to_string(&file).map_err(|err| format!("failed to read `{}`: {}", file.display(), err))?;
+        let relative = file.strip_prefix(root).unwrap_or(&file);
+        match scan::scan_file(relative, &text) {
+            Ok(found) => assertions.extend(found),
+            // A file that does not parse cannot be compiled either, so it is reported
+            // without stopping the scan.
+            Err(err) => eprintln!("warning: skipping `{}`: {}", relative.display(), err),
+        }
+    }
+
+    if json {
+        println!("{}", to_json(&assertions));
+    } else {
+        print!("{}", to_table(&assertions));
+    }
+    Ok(())
+}
+
+/// Formats the assertions as a table with one row per assertion.
+fn to_table(assertions: &[Assertion]) -> String {
+    let rows: Vec<[String; 4]> = assertions
+        .iter()
+        .map(|assertion| {
+            [
+                assertion.ty.clone(),
+                format!("{}:{}", assertion.file.display(), assertion.line),
+                assertion.expected.clone().unwrap_or_else(|| "-".to_owned()),
+                assertion.options.join(", "),
+            ]
+        })
+        .collect();
+
+    let header = ["TYPE", "LOCATION", "EXPECTED", "OPTIONS"].map(str::to_owned);
+    let mut widths = header.each_ref().map(|title| title.chars().count());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut table = String::new();
+    for row in std::iter::once(&header).chain(&rows) {
+        let line = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ");
+        table += line.trim_end();
+        table += "\n";
+    }
+    table
+}
+
+/// Formats the assertions as a JSON array of objects.
+fn to_json(assertions: &[Assertion]) -> String {
+    let objects: Vec<serde_json::Value> = assertions
+        .iter()
+        .map(|assertion| {
+            json!({
+                "type": assertion.ty,
+                "file": assertion.file.to_string_lossy().replace('\\', "/"),
+                "line": assertion.line,
+                "expected": assertion.expected,
+                "options": assertion.options,
+            })
+        })
+        .collect();
+    serde_json::to_string_pretty(&objects).unwrap()
+}
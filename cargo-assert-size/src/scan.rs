@@ -0,0 +1,278 @@
+//! Discovery of the size assertions in a crate's sources.
+//!
+//! The sources are parsed with `syn` rather than compiled, so assertions are found as
+//! written, including those behind `cfg`s that do not apply to the host.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use proc_macro2::{Spacing, Span, TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::visit::{self, Visit};
+use syn::{Attribute, GenericParam, Generics, Ident, ItemEnum, ItemImpl, ItemMod, ItemStruct, ItemType, ItemUnion, Macro, Meta};
+
+/// The options of `#[assert_size]` that are given as a bare flag, as opposed to an
+/// expected size given as a constant.
+const FLAGS: &[&str] = &["option_same_size", "no_padding", "report", "test", "snapshot", "c_export"];
+
+/// A single `#[assert_size]`, `#[size]` or `assert_sizes!` entry.
+pub struct Assertion {
+    /// The asserted type, as written.
+    pub ty: String,
+    pub file: PathBuf,
+    /// The line of the attribute or `assert_sizes!` entry.
+    pub line: usize,
+    /// The expected size as written, or `None` when it is implied by an option such as
+    /// `same_as = Type`.
+    pub expected: Option<String>,
+    /// The other arguments, such as `align = 8` or `no_padding`, as written.
+    pub options: Vec<String>,
+    /// The inline modules of the file the assertion is in, as in `["tests"]` for one in
+    /// `mod tests { ... }`.
+    pub module: Vec<String>,
+}
+
+/// Finds the Rust sources under `root`, skipping build output and hidden directories.
+pub fn find_source_files(root: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if entry.file_type()?.is_dir() {
+                if name != "target" && !name.starts_with('.') {
+                    dirs.push(path);
+                }
+            } else if name.ends_with(".rs") {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Finds the assertions in the source `text` of `file`, in the order they are written.
+pub fn scan_file(file: &Path, text: &str) -> syn::Result<Vec<Assertion>> {
+    let syntax = syn::parse_file(text)?;
+    let mut scanner = Scanner { file, assertions: Vec::new(), module: Vec::new() };
+    scanner.visit_file(&syntax);
+    Ok(scanner.assertions)
+}
+
+struct Scanner<'a> {
+    file: &'a Path,
+    assertions: Vec<Assertion>,
+    /// The inline modules being visited.
+    module: Vec<String>,
+}
+
+impl Scanner<'_> {
+    /// Records the `#[assert_size(...)]` attributes of an item declaring or naming `ty`,
+    /// and its `#[size(...)]` attribute if it derives `AssertSize`.
+    fn attributes(&mut self, attrs: &[Attribute], ty: String) {
+        let derives = attrs.iter().any(|attr| {
+            attr.path().is_ident("derive")
+                && attr
+                    .meta
+                    .require_list()
+                    .is_ok_and(|list| list.tokens.clone().into_iter().any(|token| is_ident(&token, "AssertSize")))
+        });
+        for attr in attrs {
+            let name = attr.path().segments.last().map(|segment| segment.ident.to_string());
+            let asserts = match name.as_deref() {
+                Some("assert_size") => true,
+                Some("size") => derives && attr.path().is_ident("size"),
+                _ => false,
+            };
+            if let (true, Meta::List(list)) = (asserts, &attr.meta) {
+                self.push(ty.clone(), attr.pound_token.span, list.tokens.clone());
+            }
+        }
+    }
+
+    /// Records each `Type => N` or `Type => (N, options...)` entry of `assert_sizes!`.
+    fn assert_sizes(&mut self, mac: &Macro) {
+        for entry in split_top_level(mac.tokens.clone()) {
+            let Some(arrow) = find_arrow(&entry) else {
+                continue;
+            };
+            let (ty, value) = (&entry[..arrow], &entry[arrow + 2..]);
+            let args = match value {
+                [TokenTree::Group(group)] if group.delimiter() == proc_macro2::Delimiter::Parenthesis => group.stream(),
+                _ => value.iter().cloned().collect(),
+            };
+            self.push(source_text(ty), entry[0].span(), args);
+        }
+    }
+
+    fn push(&mut self, ty: String, span: Span, args: TokenStream) {
+        let (expected, options) = split_args(args);
+        self.assertions.push(Assertion {
+            ty,
+            file: self.file.to_path_buf(),
+            line: span.start().line,
+            expected,
+            options,
+            module: self.module.clone(),
+        });
+    }
+}
+
+impl<'ast> Visit<'ast> for Scanner<'_> {
+    fn visit_item_struct(&mut self, item: &'ast ItemStruct) {
+        self.attributes(&item.attrs, type_name(&item.ident, &item.generics));
+        visit::visit_item_struct(self, item);
+    }
+
+    fn visit_item_enum(&mut self, item: &'ast ItemEnum) {
+        self.attributes(&item.attrs, type_name(&item.ident, &item.generics));
+        visit::visit_item_enum(self, item);
+    }
+
+    fn visit_item_union(&mut self, item: &'ast ItemUnion) {
+        self.attributes(&item.attrs, type_name(&item.ident, &item.generics));
+        visit::visit_item_union(self, item);
+    }
+
+    fn visit_item_type(&mut self, item: &'ast ItemType) {
+        self.attributes(&item.attrs, tokens_text(&item.ty));
+        visit::visit_item_type(self, item);
+    }
+
+    fn visit_item_impl(&mut self, item: &'ast ItemImpl) {
+        self.attributes(&item.attrs, tokens_text(&item.self_ty));
+        visit::visit_item_impl(self, item);
+    }
+
+    fn visit_item_mod(&mut self, item: &'ast ItemMod) {
+        self.module.push(item.ident.to_string());
+        visit::visit_item_mod(self, item);
+        self.module.pop();
+    }
+
+    fn visit_macro(&mut self, mac: &'ast Macro) {
+        if mac.path.segments.last().is_some_and(|segment| segment.ident == "assert_sizes") {
+            self.assert_sizes(mac);
+        }
+        visit::visit_macro(self, mac);
+    }
+}
+
+/// The name of a type definition along with the names of its generic parameters, as in
+/// `Wrapper<'a, T, N>`.
+fn type_name(ident: &Ident, generics: &Generics) -> String {
+    if generics.params.is_empty() {
+        ident.to_string()
+    } else {
+        let params: Vec<String> = generics
+            .params
+            .iter()
+            .map(|param| match param {
+                GenericParam::Lifetime(param) => param.lifetime.to_string(),
+                GenericParam::Type(param) => param.ident.to_string(),
+                GenericParam::Const(param) => param.ident.to_string(),
+            })
+            .collect();
+        format!("{}<{}>", ident, params.join(", "))
+    }
+}
+
+/// Splits the arguments of an assertion into the expected size and the options. The
+/// expected size may span several arguments when it is given as target arms, as in
+/// `unix => 8, _ => 4`.
+fn split_args(args: TokenStream) -> (Option<String>, Vec<String>) {
+    let arguments = split_top_level(args);
+    let size_len = arguments.iter().take_while(|argument| !is_option(argument)).count();
+    let expected = (size_len > 0).then(|| {
+        arguments[..size_len]
+            .iter()
+            .map(|argument| source_text(argument))
+            .collect::<Vec<_>>()
+            .join(", ")
+    });
+    let options = arguments[size_len..].iter().map(|argument| source_text(argument)).collect();
+    (expected, options)
+}
+
+/// Whether an argument is an option such as `align = 8` or `no_padding`, rather than part
+/// of the expected size.
+fn is_option(argument: &[TokenTree]) -> bool {
+    // A target arm such as `target_pointer_width = "64" => 8` starts like an option.
+    if find_arrow(argument).is_some() {
+        return false;
+    }
+    match argument {
+        [TokenTree::Ident(ident)] => FLAGS.iter().any(|flag| ident == flag),
+        [TokenTree::Ident(_), eq, ..] => is_punct(eq, '=', Spacing::Alone),
+        _ => false,
+    }
+}
+
+/// Finds the first `=>` in `tokens` that is not nested in a group.
+fn find_arrow(tokens: &[TokenTree]) -> Option<usize> {
+    tokens
+        .windows(2)
+        .position(|pair| is_punct(&pair[0], '=', Spacing::Joint) && is_punct(&pair[1], '>', Spacing::Alone))
+}
+
+/// Splits `tokens` at the commas that are not nested in a group or in the angle brackets
+/// of generic arguments, dropping empty trailing parts.
+pub(crate) fn split_top_level(tokens: TokenStream) -> Vec<Vec<TokenTree>> {
+    let mut parts = vec![Vec::new()];
+    let mut depth = 0usize;
+    let mut previous: Option<TokenTree> = None;
+    for token in tokens {
+        let part = parts.last_mut().unwrap();
+        if let TokenTree::Punct(punct) = &token {
+            // A `<` starting a part is a bound such as `< 64`, and `<=`, `=>` and `->`
+            // are operators rather than angle brackets.
+            let arrow = previous.as_ref().is_some_and(|previous| {
+                is_punct(previous, '=', Spacing::Joint) || is_punct(previous, '-', Spacing::Joint)
+            });
+            match punct.as_char() {
+                ',' if depth == 0 => {
+                    parts.push(Vec::new());
+                    previous = None;
+                    continue;
+                }
+                '<' if !part.is_empty() && punct.spacing() == Spacing::Alone => depth += 1,
+                '>' if !arrow => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+        part.push(token.clone());
+        previous = Some(token);
+    }
+    parts.retain(|part| !part.is_empty());
+    parts
+}
+
+/// The text of `tokens` as written in the source, or as printed if it is unavailable.
+pub(crate) fn source_text(tokens: &[TokenTree]) -> String {
+    let (Some(first), Some(last)) = (tokens.first(), tokens.last()) else {
+        return String::new();
+    };
+    first
+        .span()
+        .join(last.span())
+        .and_then(|span| span.source_text())
+        .unwrap_or_else(|| tokens.iter().cloned().collect::<TokenStream>().to_string())
+}
+
+/// The text of a syntax tree node as written in the source.
+fn tokens_text(node: impl ToTokens) -> String {
+    source_text(&node.into_token_stream().into_iter().collect::<Vec<_>>())
+}
+
+fn is_punct(token: &TokenTree, c: char, spacing: Spacing) -> bool {
+    matches!(token, TokenTree::Punct(punct) if punct.as_char() == c && punct.spacing() == spacing)
+}
+
+fn is_ident(token: &TokenTree, name: &str) -> bool {
+    matches!(token, TokenTree::Ident(ident) if ident == name)
+}
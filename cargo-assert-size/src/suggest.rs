@@ -0,0 +1,521 @@
+//! `cargo assert-size suggest`: proposes `#[assert_size]` annotations for the type
+//! definitions that have none.
+//!
+//! Sizes are measured by the compiler rather than computed from the sources: in a copy of
+//! the workspace under the target directory, each candidate is annotated with a probe that
+//! cannot hold, an expected size larger than any type can have, and its actual size is
+//! read from the failure reported by `cargo check`. The sources themselves are only
+//! written with `--write`, and the suggested sizes are those of the host target.
+//!
+//! A type counts as asserted if an `#[assert_size]` or `#[size]` attribute, an
+//! `assert_sizes!` entry or an `#[assert_size]` impl block names it by a path relative to
+//! its module. Imports are not followed, so a type asserted under an imported name is
+//! suggested again.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use proc_macro2::TokenTree;
+use quote::ToTokens;
+use syn::{Attribute, Item, Type, UseTree};
+
+use crate::check::{self, Check};
+use crate::scan;
+
+/// The expected size of a probe, which no type can have on any target since sizes are at
+/// most `isize::MAX`.
+const PROBE: &str = "usize::MAX";
+
+/// Which type definitions get an annotation, by their visibility.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+    /// Only those declared `pub`.
+    Pub,
+    /// Those declared `pub` or `pub(...)`.
+    Crate,
+    /// All of them, including private ones.
+    All,
+}
+
+/// The filters given on the command line. An empty list lets every type through.
+pub struct Filters {
+    /// Module paths such as `net::packet`, which match the module and those nested in it.
+    pub modules: Vec<Vec<String>>,
+    /// `repr` hints such as `C` or `u8`, of which a type must have one.
+    pub reprs: Vec<String>,
+    pub visibility: Visibility,
+}
+
+/// A type definition to annotate.
+struct Candidate {
+    /// The path of the type, as in `crate::net::Header`.
+    path: String,
+    /// The byte offset of the line the annotation goes before, which is that of the first
+    /// attribute other than doc comments, or of the item itself.
+    offset: usize,
+    /// The indentation of that line.
+    indent: String,
+    /// The path of the attribute, which is `assert_size` if the module imports it.
+    attribute: &'static str,
+    /// The measured size.
+    size: Option<u128>,
+}
+
+/// A source file with candidates.
+struct Source {
+    /// The canonical path of the file.
+    file: PathBuf,
+    /// The path of the file relative to the scanned directory, for output.
+    relative: PathBuf,
+    text: String,
+    candidates: Vec<Candidate>,
+}
+
+/// Finds the type definitions under `root` that match `filters` and are not asserted yet,
+/// measures them by checking the crate or workspace of `manifest`, and prints the
+/// annotations as a diff, or inserts them with `write`.
+pub fn suggest(manifest: Option<&Path>, root: &Path, filters: &Filters, write: bool) -> Result<(), String> {
+    let files = scan::find_source_files(root).map_err(|err| format!("failed to read `{}`: {}", root.display(), err))?;
+    let mut texts = Vec::new();
+    let mut asserted = HashSet::new();
+    for file in files {
+        let text = fs::read_to_string(&file).map_err(|err| format!("failed to read `{}`: {}", file.display(), err))?;
+        let relative = file.strip_prefix(root).unwrap_or(&file).to_path_buf();
+        // Only the sources of targets are candidates, rather than tests or build scripts.
+        let Some(module) = module_path(&relative) else {
+            continue;
+        };
+        match scan::scan_file(&relative, &text) {
+            Ok(assertions) => asserted.extend(assertions.iter().filter_map(|assertion| {
+                let module: Vec<String> = module.iter().chain(&assertion.module).cloned().collect();
+                resolve(&assertion.ty, &module)
+            })),
+            Err(err) => {
+                eprintln!("warning: skipping `{}`: {}", relative.display(), err);
+                continue;
+            }
+        }
+        texts.push((file, relative, module, text));
+    }
+
+    let mut collector = Collector { filters, asserted: &asserted, candidates: Vec::new(), skipped: 0, generic: 0 };
+    let mut sources = Vec::new();
+    for (file, relative, mut module, text) in texts {
+        let Ok(syntax) = syn::parse_file(&text) else {
+            continue;
+        };
+        collector.items(&text, &syntax.items, &mut module);
+        let candidates = std::mem::take(&mut collector.candidates);
+        if !candidates.is_empty() {
+            let file = fs::canonicalize(&file).map_err(|err| format!("failed to read `{}`: {}", file.display(), err))?;
+            sources.push(Source { file, relative, text, candidates });
+        }
+    }
+    let (skipped, generic) = (collector.skipped, collector.generic);
+    if sources.is_empty() {
+        eprintln!("no unannotated types found ({} already asserted, {} generic)", skipped, generic);
+        return Ok(());
+    }
+
+    measure(manifest, &mut sources)?;
+
+    let mut suggested = 0;
+    for source in &sources {
+        for candidate in source.candidates.iter().filter(|candidate| candidate.size.is_none()) {
+            let line = source.text[..candidate.offset].matches('\n').count() + 1;
+            eprintln!(
+                "warning: could not measure `{}` at {}:{}, which may be behind a `cfg` that does not apply to the host, or in a crate that does not depend on `assert-size-derive`",
+                candidate.path,
+                source.relative.display(),
+                line
+            );
+        }
+        let measured = source.candidates.iter().filter(|candidate| candidate.size.is_some()).count();
+        if measured == 0 {
+            continue;
+        }
+        if write {
+            let (annotated, _) = annotate(source, false);
+            fs::write(&source.file, annotated)
+                .map_err(|err| format!("failed to write `{}`: {}", source.file.display(), err))?;
+            eprintln!("added {} annotation(s) to `{}`", measured, source.relative.display());
+        } else {
+            print!("{}", diff(source));
+        }
+        suggested += measured;
+    }
+    eprintln!(
+        "suggested {} annotation(s) with the sizes of the host target ({} already asserted, {} generic)",
+        suggested, skipped, generic
+    );
+    Ok(())
+}
+
+/// Measures the candidates of `sources` with probes, in a copy of the workspace that is
+/// kept in the target directory, so that later runs only copy the files that changed.
+///
+/// A crate that fails to compile hides the probes in the crates depending on it, so
+/// measured probes are replaced by their annotations and the rest are checked again,
+/// until a check measures nothing new.
+fn measure(manifest: Option<&Path>, sources: &mut [Source]) -> Result<(), String> {
+    let metadata = check::metadata(manifest)?;
+    let canonical = |path: &Path| fs::canonicalize(path).map_err(|err| format!("failed to read `{}`: {}", path.display(), err));
+    let root = canonical(&metadata.workspace_root)?;
+    let target_directory = &metadata.target_directory;
+    let scratch = target_directory.join("assert-size-suggest");
+    // The build output may not exist yet, or be outside of the workspace.
+    let skip = fs::canonicalize(target_directory).unwrap_or_else(|_| target_directory.clone());
+    sync_dir(&root, &scratch, &root, &skip)
+        .map_err(|err| format!("failed to copy the workspace to `{}`: {}", scratch.display(), err))?;
+    let scratch = canonical(&scratch)?;
+
+    // The copy of each source, or `None` for one outside of the workspace.
+    let copies: Vec<Option<PathBuf>> = sources
+        .iter()
+        .map(|source| source.file.strip_prefix(&root).ok().map(|relative| scratch.join(relative)))
+        .collect();
+    let mut stderr = None;
+    loop {
+        // The candidate of each probe, by the file and byte offset of its expected value.
+        let mut probes = HashMap::new();
+        for (index, (source, copy)) in sources.iter().zip(&copies).enumerate() {
+            let Some(copy) = copy else {
+                continue;
+            };
+            let (probed, offsets) = annotate(source, true);
+            fs::write(copy, probed).map_err(|err| format!("failed to write `{}`: {}", copy.display(), err))?;
+            for (candidate, offset) in offsets {
+                probes.insert((copy.clone(), offset), (index, candidate));
+            }
+        }
+        if probes.is_empty() {
+            break;
+        }
+
+        let Check { mismatches, failure } = check::check(Some(&scratch.join("Cargo.toml")), &scratch, Some(target_directory))?;
+        let mut measured = false;
+        for mismatch in mismatches {
+            if let Some(&(index, candidate)) = probes.get(&(mismatch.file, mismatch.start)) {
+                let candidate = &mut sources[index].candidates[candidate];
+                measured |= candidate.size.is_none();
+                candidate.size = Some(mismatch.actual);
+            }
+        }
+        stderr = failure;
+        if !measured {
+            break;
+        }
+    }
+    // The probed copies are put back, so that they are not copied again by the next run.
+    for (source, copy) in sources.iter().zip(&copies) {
+        if let Some(copy) = copy {
+            let _ = fs::write(copy, &source.text);
+        }
+    }
+
+    if let (Some(stderr), true) = (stderr, sources.iter().all(|source| source.candidates.iter().all(|c| c.size.is_none()))) {
+        eprint!("{}", stderr);
+        eprintln!("warning: `cargo check` fails, but no sizes could be measured");
+    }
+    Ok(())
+}
+
+/// Makes the directory `to` a copy of the directory `from` in the workspace at `root`,
+/// leaving out hidden directories and `skip`. Files are only copied if they are newer than
+/// their copy or differ in size, and files that no longer exist are removed.
+fn sync_dir(from: &Path, to: &Path, root: &Path, skip: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    let mut copied = HashSet::new();
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let path = entry.path();
+        let copy = to.join(entry.file_name());
+        if path.is_dir() {
+            if path != skip && !entry.file_name().to_string_lossy().starts_with('.') {
+                sync_dir(&path, &copy, root, skip)?;
+                copied.insert(entry.file_name());
+            }
+            continue;
+        }
+        copied.insert(entry.file_name());
+        if entry.file_name() == "Cargo.toml" {
+            // Manifests are compared by content, since relative paths are rewritten.
+            let text = absolute_paths(&fs::read_to_string(&path)?, from, root);
+            if fs::read_to_string(&copy).ok().as_ref() != Some(&text) {
+                fs::write(&copy, text)?;
+            }
+        } else if !is_fresh(&path, &copy) {
+            fs::copy(&path, &copy)?;
+        }
+    }
+    for entry in fs::read_dir(to)? {
+        let entry = entry?;
+        if !copied.contains(&entry.file_name()) {
+            if entry.file_type()?.is_dir() {
+                fs::remove_dir_all(entry.path())?;
+            } else {
+                fs::remove_file(entry.path())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether `copy` is at least as new as `file` and has its size.
+fn is_fresh(file: &Path, copy: &Path) -> bool {
+    match (fs::metadata(file), fs::metadata(copy)) {
+        (Ok(file), Ok(copy)) => {
+            file.len() == copy.len()
+                && matches!((file.modified(), copy.modified()), (Ok(file), Ok(copy)) if copy >= file)
+        }
+        _ => false,
+    }
+}
+
+/// Rewrites the relative `path` values of the manifest `text` in the directory `dir` to
+/// absolute paths if they lead out of the workspace at `root`, so that path dependencies
+/// outside of the workspace are still found from its copy.
+fn absolute_paths(text: &str, dir: &Path, root: &Path) -> String {
+    let mut rewritten = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("path") {
+        let (before, after) = rest.split_at(start + "path".len());
+        rewritten += before;
+        rest = after;
+        // Only a `path` key, rather than a key such as `lib-path` or a value.
+        let is_key = !before[..start].ends_with(|c: char| c.is_alphanumeric() || matches!(c, '_' | '-' | '"' | '\''));
+        let value = rest.trim_start_matches([' ', '\t']);
+        let Some(value) = value.strip_prefix('=').map(|value| value.trim_start_matches([' ', '\t'])) else {
+            continue;
+        };
+        let Some(quote) = value.chars().next().filter(|&c| is_key && (c == '"' || c == '\'')) else {
+            continue;
+        };
+        let Some(len) = value[1..].find(quote) else {
+            continue;
+        };
+        let relative = Path::new(&value[1..1 + len]);
+        if relative.is_relative() {
+            if let Ok(absolute) = fs::canonicalize(dir.join(relative)) {
+                if !absolute.starts_with(root) {
+                    rewritten += &rest[..rest.len() - value.len()];
+                    rewritten += &format!("{:?}", absolute.display().to_string());
+                    rest = &value[len + 2..];
+                }
+            }
+        }
+    }
+    rewritten += rest;
+    rewritten
+}
+
+/// The text of `source` with the annotations of its measured candidates, along with a
+/// probe for each other candidate with `probes`. The byte offsets of the expected values
+/// of the probes are returned by candidate index.
+fn annotate(source: &Source, probes: bool) -> (String, Vec<(usize, usize)>) {
+    let mut text = String::with_capacity(source.text.len());
+    let mut offsets = Vec::new();
+    let mut copied = 0;
+    for (index, candidate) in source.candidates.iter().enumerate() {
+        let value = match candidate.size {
+            Some(size) => size.to_string(),
+            None if probes => PROBE.to_owned(),
+            None => continue,
+        };
+        text += &source.text[copied..candidate.offset];
+        copied = candidate.offset;
+        text += &candidate.indent;
+        text += "#[";
+        text += candidate.attribute;
+        text += "(";
+        if candidate.size.is_none() {
+            offsets.push((index, text.len()));
+        }
+        text += &value;
+        text += ")]\n";
+    }
+    text += &source.text[copied..];
+    (text, offsets)
+}
+
+/// A diff inserting the annotations of `source`, with the line each one goes before.
+fn diff(source: &Source) -> String {
+    let file = source.relative.display();
+    let mut diff = format!("--- {}\n+++ {}\n", file, file);
+    let mut inserted = 0;
+    for candidate in &source.candidates {
+        let Some(size) = candidate.size else {
+            continue;
+        };
+        let line = source.text[..candidate.offset].matches('\n').count() + 1;
+        let context = source.text[candidate.offset..].lines().next().unwrap_or("");
+        diff += &format!(
+            "@@ -{},1 +{},2 @@\n+{}#[{}({})]\n {}\n",
+            line,
+            line + inserted,
+            candidate.indent,
+            candidate.attribute,
+            size,
+            context
+        );
+        inserted += 1;
+    }
+    diff
+}
+
+/// Finds the candidates among the items of a source file.
+struct Collector<'a> {
+    filters: &'a Filters,
+    /// The paths of the types asserted anywhere, as in `crate::net::Header`.
+    asserted: &'a HashSet<String>,
+    candidates: Vec<Candidate>,
+    /// The number of types left out because they are asserted already.
+    skipped: usize,
+    /// The number of types left out because they are generic, and so have no single size.
+    generic: usize,
+}
+
+impl Collector<'_> {
+    /// Collects the candidates among `items` of the module `module` in the source `text`, and of the modules
+    /// declared inline among them. Types declared in function bodies are left out.
+    fn items(&mut self, text: &str, items: &[Item], module: &mut Vec<String>) {
+        let attribute = if imports_assert_size(items) { "assert_size" } else { "assert_size_derive::assert_size" };
+        for item in items {
+            match item {
+                Item::Struct(_) | Item::Enum(_) | Item::Union(_) => self.item(text, item, module, attribute),
+                Item::Mod(item) => {
+                    if let Some((_, items)) = &item.content {
+                        module.push(item.ident.to_string());
+                        self.items(text, items, module);
+                        module.pop();
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Collects a struct, enum or union definition if it is a candidate.
+    fn item(&mut self, text: &str, item: &Item, module: &[String], attribute: &'static str) {
+        let (attrs, vis, ident, generics, keyword) = match item {
+            Item::Struct(item) => (&item.attrs, &item.vis, &item.ident, &item.generics, item.struct_token.span),
+            Item::Enum(item) => (&item.attrs, &item.vis, &item.ident, &item.generics, item.enum_token.span),
+            Item::Union(item) => (&item.attrs, &item.vis, &item.ident, &item.generics, item.union_token.span),
+            _ => return,
+        };
+        let visible = match self.filters.visibility {
+            Visibility::Pub => matches!(vis, syn::Visibility::Public(_)),
+            Visibility::Crate => !matches!(vis, syn::Visibility::Inherited),
+            Visibility::All => true,
+        };
+        let in_module = self.filters.modules.is_empty()
+            || self.filters.modules.iter().any(|filter| module.starts_with(filter));
+        let reprs = repr_hints(attrs);
+        let has_repr = self.filters.reprs.is_empty() || self.filters.reprs.iter().any(|repr| reprs.contains(repr));
+        if !(visible && in_module && has_repr) {
+            return;
+        }
+        let mut path = String::from("crate");
+        for segment in module {
+            path += "::";
+            path += segment;
+        }
+        path += &format!("::{}", ident);
+        if self.asserted.contains(&path) {
+            self.skipped += 1;
+            return;
+        }
+        if !generics.params.is_empty() {
+            self.generic += 1;
+            return;
+        }
+        let start = attrs
+            .iter()
+            .find(|attr| !attr.path().is_ident("doc"))
+            .map(|attr| attr.pound_token.span)
+            .or_else(|| vis.to_token_stream().into_iter().next().map(|token| token.span()))
+            .unwrap_or(keyword)
+            .start();
+        let offset = text.split_inclusive('\n').take(start.line - 1).map(str::len).sum::<usize>();
+        let line = &text[offset..];
+        let indent: String = line.chars().take_while(|c| *c == ' ' || *c == '\t').collect();
+        // An item sharing its first line with something else cannot get an attribute
+        // line of its own.
+        if indent.chars().count() != start.column {
+            eprintln!("warning: skipping `{}`, which does not start its own line", path);
+            return;
+        }
+        self.candidates.push(Candidate { path, offset, indent, attribute, size: None });
+    }
+}
+
+/// The path of the module of a file under a `src` directory, relative to the crate root,
+/// as in `["net"]` for `src/net.rs` or `src/net/mod.rs`. Files outside of `src` give `None`.
+fn module_path(relative: &Path) -> Option<Vec<String>> {
+    let components: Vec<String> =
+        relative.iter().map(|component| component.to_string_lossy().into_owned()).collect();
+    let src = components.iter().rposition(|component| component == "src")?;
+    let mut module: Vec<String> = components[src + 1..].to_vec();
+    let file = module.pop()?;
+    match file.strip_suffix(".rs")? {
+        "lib" | "main" if module.is_empty() => {}
+        "mod" => {}
+        name => module.push(name.to_owned()),
+    }
+    Some(module)
+}
+
+/// Whether the `use` items among `items` import the `assert_size` attribute.
+fn imports_assert_size(items: &[Item]) -> bool {
+    fn imports(tree: &UseTree, from_derive: bool) -> bool {
+        match tree {
+            UseTree::Path(path) => imports(&path.tree, path.ident == "assert_size_derive"),
+            UseTree::Name(name) => name.ident == "assert_size",
+            UseTree::Rename(rename) => rename.rename == "assert_size",
+            UseTree::Glob(_) => from_derive,
+            UseTree::Group(group) => group.items.iter().any(|tree| imports(tree, from_derive)),
+        }
+    }
+    items.iter().any(|item| matches!(item, Item::Use(item) if imports(&item.tree, false)))
+}
+
+/// The names of the `repr` hints of an item, as in `["C", "align"]` for
+/// `#[repr(C, align(8))]`.
+fn repr_hints(attrs: &[Attribute]) -> Vec<String> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("repr"))
+        .filter_map(|attr| attr.meta.require_list().ok())
+        .flat_map(|list| scan::split_top_level(list.tokens.clone()))
+        .filter_map(|hint| match hint.first() {
+            Some(TokenTree::Ident(ident)) => Some(ident.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The path of an asserted type in the module `module`, as in `crate::net::Header` for
+/// `Header<u8>` or `super::Header` asserted in `net::tests`. Types other than paths give
+/// `None`.
+fn resolve(ty: &str, module: &[String]) -> Option<String> {
+    let Ok(Type::Path(ty)) = syn::parse_str(ty) else {
+        return None;
+    };
+    if ty.qself.is_some() || ty.path.leading_colon.is_some() {
+        return None;
+    }
+    let mut path = module.to_vec();
+    for (index, segment) in ty.path.segments.iter().enumerate() {
+        match segment.ident.to_string().as_str() {
+            "crate" if index == 0 => path.clear(),
+            "self" if index == 0 => {}
+            "super" => {
+                path.pop()?;
+            }
+            name => path.push(name.to_owned()),
+        }
+    }
+    Some(format!("crate::{}", path.join("::")))
+}
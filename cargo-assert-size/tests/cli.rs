@@ -0,0 +1,232 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Runs `cargo assert-size` with `args` on the sample crate in `tests/fixtures`.
+fn run(args: &[&str]) -> String {
+    let manifest = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sample/Cargo.toml");
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-assert-size"))
+        .arg("assert-size")
+        .args(args)
+        .arg("--manifest-path")
+        .arg(manifest)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn lists_assertions_as_table() {
+    assert_eq!(
+        run(&["list"]),
+        "\
+TYPE             LOCATION   EXPECTED                                   OPTIONS
+Header           lib.rs:3   16                                         align = 8
+Slice            lib.rs:10  target_pointer_width = \"64\" => 16, _ => 8  no_padding
+Mirror           lib.rs:17  -                                          same_as = Header
+Wrapper<T>       lib.rs:24  <= 8                                       for = [Wrapper<u32>, Wrapper<u64>]
+u64              lib.rs:31  8
+Option<Box<u8>>  lib.rs:32  8                                          option_same_size
+"
+    );
+}
+
+#[test]
+fn lists_assertions_as_json() {
+    let json: serde_json::Value = serde_json::from_str(&run(&["--json"])).unwrap();
+    let assertions = json.as_array().unwrap();
+    assert_eq!(
+        assertions[0],
+        serde_json::json!({"type": "Header", "file": "lib.rs", "line": 3, "expected": "16", "options": ["align = 8"]})
+    );
+    assert!(assertions.contains(
+        &serde_json::json!({"type": "Mirror", "file": "lib.rs", "line": 17, "expected": null, "options": ["same_as = Header"]})
+    ));
+    assert!(assertions.iter().any(|assertion| assertion["expected"] == "target_pointer_width = \"64\" => 16, _ => 8"));
+}
+
+/// Copies the crate in `tests/fixtures/<fixture>` to a scratch directory, returning its
+/// manifest. A `lib.rs` at the top of the fixture is the root of the library, and `src`
+/// is copied along if there is one.
+fn copy_fixture(fixture: &str, name: &str) -> PathBuf {
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(fixture);
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("src")).unwrap();
+    let lib = if fixture.join("lib.rs").exists() {
+        fs::copy(fixture.join("lib.rs"), dir.join("lib.rs")).unwrap();
+        "\n[lib]\npath = \"lib.rs\"\n"
+    } else {
+        for entry in fs::read_dir(fixture.join("src")).unwrap() {
+            let entry = entry.unwrap();
+            fs::copy(entry.path(), dir.join("src").join(entry.file_name())).unwrap();
+        }
+        ""
+    };
+    let derive = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+    fs::write(
+        dir.join("Cargo.toml"),
+        format!(
+            "[package]\nname = {:?}\nversion = \"0.0.0\"\nedition = \"2021\"\n{}\n\
+             [dependencies]\nassert-size-derive = {{ path = {:?} }}\n\n[workspace]\n",
+            name, lib, derive
+        ),
+    )
+    .unwrap();
+    // The workspace's lockfile pins the same dependencies without resolving them again.
+    let _ = fs::copy(derive.join("Cargo.lock"), dir.join("Cargo.lock"));
+    dir.join("Cargo.toml")
+}
+
+/// Runs `cargo assert-size` with `args` on the crate of `manifest`, which is checked.
+fn run_checked(manifest: &Path, args: &[&str]) -> String {
+    let output = checked_command(manifest, args).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Like [`run_checked`], but expects `cargo assert-size` to fail, returning its errors.
+fn run_checked_failing(manifest: &Path, args: &[&str]) -> String {
+    let output = checked_command(manifest, args).output().unwrap();
+    assert!(!output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+    String::from_utf8(output.stderr).unwrap()
+}
+
+fn checked_command(manifest: &Path, args: &[&str]) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_cargo-assert-size"));
+    command
+        .arg("assert-size")
+        .args(args)
+        .arg("--manifest-path")
+        .arg(manifest)
+        .env("CARGO_TARGET_DIR", Path::new(env!("CARGO_TARGET_TMPDIR")).join("bless-target"));
+    command
+}
+
+#[test]
+fn bless_rewrites_expected_sizes() {
+    let manifest = copy_fixture("bless", "bless");
+    run_checked(&manifest, &["bless"]);
+    let blessed = fs::read_to_string(manifest.with_file_name("lib.rs")).unwrap();
+    let expected = fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/bless/lib.rs"))
+        .unwrap()
+        .replace("#[assert_size(12, align = 8)]", "#[assert_size(16, align = 8)]")
+        .replace("0x4usize", "0x8usize")
+        .replace("Pair<u8> => 1", "Pair<u8> => 2")
+        .replace("(u8, u32) => 5", "(u8, u32) => 8");
+    assert_eq!(blessed, expected);
+}
+
+#[test]
+fn bless_dry_run_prints_diff() {
+    let manifest = copy_fixture("bless", "bless-dry-run");
+    let before = fs::read_to_string(manifest.with_file_name("lib.rs")).unwrap();
+    assert_eq!(
+        run_checked(&manifest, &["bless", "--dry-run"]),
+        "\
+--- lib.rs
++++ lib.rs
+@@ -4 +4 @@
+-#[assert_size(12, align = 8)]
++#[assert_size(16, align = 8)]
+@@ -20 +20 @@
+-#[assert_size(<= 8, for = [Pair<u8> => 1, Pair<u32>])]
++#[assert_size(<= 8, for = [Pair<u8> => 2, Pair<u32>])]
+@@ -25 +25 @@
+-    (u8, u32) => 5,
++    (u8, u32) => 8,
+"
+    );
+    assert_eq!(fs::read_to_string(manifest.with_file_name("lib.rs")).unwrap(), before);
+}
+
+#[test]
+fn bless_leaves_alignments_and_conflicting_sizes() {
+    let manifest = copy_fixture("bless-unblessable", "bless-unblessable");
+    let before = fs::read_to_string(manifest.with_file_name("lib.rs")).unwrap();
+    let stderr = run_checked_failing(&manifest, &["bless"]);
+    assert!(
+        stderr.contains("warning: the expected size at lib.rs:10 is 4 or 8 bytes depending on the target, so it cannot be blessed\n"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("error: 1 expected size(s) must be updated by hand\n"), "{}", stderr);
+    assert_eq!(fs::read_to_string(manifest.with_file_name("lib.rs")).unwrap(), before);
+}
+
+#[test]
+fn suggest_prints_annotations() {
+    let manifest = copy_fixture("suggest", "suggest");
+    let src = manifest.with_file_name("src");
+    let before = fs::read_to_string(src.join("lib.rs")).unwrap();
+    assert_eq!(
+        run_checked(&manifest, &["suggest"]),
+        "\
+--- src/lib.rs
++++ src/lib.rs
+@@ -6,1 +6,2 @@
++#[assert_size(8)]
+ #[derive(Clone, Copy)]
+@@ -26,1 +27,2 @@
++#[assert_size(1)]
+ #[repr(u8)]
+@@ -35,1 +37,2 @@
++    #[assert_size_derive::assert_size(8)]
+     pub struct Circle {
+--- src/net.rs
++++ src/net.rs
+@@ -2,1 +2,2 @@
++#[assert_size_derive::assert_size(4)]
+ #[repr(C)]
+@@ -8,1 +9,2 @@
++#[assert_size_derive::assert_size(4)]
+ pub union Bits {
+@@ -14,1 +16,2 @@
++#[assert_size_derive::assert_size(4)]
+ pub struct Vec {
+"
+    );
+    assert_eq!(fs::read_to_string(src.join("lib.rs")).unwrap(), before);
+}
+
+#[test]
+fn suggest_writes_filtered_annotations() {
+    let manifest = copy_fixture("suggest", "suggest-write");
+    let src = manifest.with_file_name("src");
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/suggest/src");
+    run_checked(&manifest, &["suggest", "--write", "--module", "crate::net", "--repr", "C"]);
+    run_checked(&manifest, &["suggest", "--write", "--visibility", "crate", "--repr", "u8", "--repr", "Rust"]);
+
+    let net = fs::read_to_string(fixture.join("net.rs"))
+        .unwrap()
+        .replace("#[repr(C)]", "#[assert_size_derive::assert_size(4)]\n#[repr(C)]");
+    assert_eq!(fs::read_to_string(src.join("net.rs")).unwrap(), net);
+    let lib = fs::read_to_string(fixture.join("lib.rs")).unwrap().replace("#[repr(u8)]", "#[assert_size(1)]\n#[repr(u8)]");
+    assert_eq!(fs::read_to_string(src.join("lib.rs")).unwrap(), lib);
+}
+
+#[test]
+fn suggest_finds_relative_path_dependencies() {
+    let manifest = copy_fixture("suggest-sibling", "suggest-sibling");
+    // A crate next to the fixture, which the copy of the fixture must still find.
+    let sibling = manifest.parent().unwrap().with_file_name("suggest-sibling-dep");
+    fs::create_dir_all(sibling.join("src")).unwrap();
+    fs::write(sibling.join("Cargo.toml"), "[package]\nname = \"sibling\"\nversion = \"0.0.0\"\nedition = \"2021\"\n").unwrap();
+    fs::write(sibling.join("src/lib.rs"), "pub struct Shared(pub u64);\n").unwrap();
+    let text = fs::read_to_string(&manifest)
+        .unwrap()
+        .replace("[dependencies]\n", "[dependencies]\nsibling = { path = \"../suggest-sibling-dep\" }\n");
+    fs::write(&manifest, text).unwrap();
+
+    assert_eq!(
+        run_checked(&manifest, &["suggest"]),
+        "\
+--- src/lib.rs
++++ src/lib.rs
+@@ -2,1 +2,2 @@
++#[assert_size_derive::assert_size(8)]
+ pub struct Holder(pub sibling::Shared);
+"
+    );
+}
@@ -0,0 +1,15 @@
+use assert_size_derive::assert_size;
+
+// Alignments are not blessed, even when the size is.
+#[assert_size(8, align = 4)]
+pub struct Aligned {
+    value: u64,
+}
+
+// The unit tests see a different size than the library does.
+#[assert_size(2)]
+pub struct Counters {
+    hits: u32,
+    #[cfg(test)]
+    misses: u32,
+}
@@ -0,0 +1,26 @@
+use assert_size_derive::{assert_size, assert_sizes};
+
+// The expected sizes are from before `length` grew to 64 bits.
+#[assert_size(12, align = 8)]
+#[repr(C)]
+pub struct Header {
+    kind: u32,
+    length: u64,
+}
+
+#[assert_size(
+    0x4usize,
+    level = "warn",
+)]
+pub struct Word(pub u64);
+
+#[assert_size(<= 64)]
+pub struct Unchanged(pub [u8; 8]);
+
+#[assert_size(<= 8, for = [Pair<u8> => 1, Pair<u32>])]
+pub struct Pair<T>(pub T, pub T);
+
+assert_sizes! {
+    u64 => 8,
+    (u8, u32) => 5,
+}
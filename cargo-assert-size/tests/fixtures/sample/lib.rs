@@ -0,0 +1,33 @@
+use assert_size_derive::{AssertSize, assert_size, assert_sizes};
+
+#[assert_size(16, align = 8)]
+#[repr(C)]
+pub struct Header {
+    kind: u64,
+    length: u64,
+}
+
+#[assert_size(target_pointer_width = "64" => 16, _ => 8, no_padding)]
+pub struct Slice {
+    ptr: *const u8,
+    len: usize,
+}
+
+#[derive(AssertSize)]
+#[size(same_as = Header)]
+pub struct Mirror {
+    kind: u64,
+    length: u64,
+}
+
+mod nested {
+    #[assert_size(<= 8, for = [Wrapper<u32>, Wrapper<u64>])]
+    pub struct Wrapper<T> {
+        value: T,
+    }
+}
+
+assert_sizes! {
+    u64 => 8,
+    Option<Box<u8>> => (8, option_same_size),
+}
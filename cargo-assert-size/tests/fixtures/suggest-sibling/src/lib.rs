@@ -0,0 +1,2 @@
+/// Holds a type from a crate next to this one, outside of its workspace.
+pub struct Holder(pub sibling::Shared);
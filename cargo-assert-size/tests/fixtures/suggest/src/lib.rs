@@ -0,0 +1,42 @@
+use assert_size_derive::{assert_size, assert_sizes};
+
+pub mod net;
+
+/// A point on the screen.
+#[derive(Clone, Copy)]
+pub struct Point {
+    pub x: u32,
+    pub y: u32,
+}
+
+#[assert_size(4)]
+pub struct Asserted(pub u32);
+
+pub struct Listed(pub u16);
+
+assert_sizes! {
+    Listed => 2,
+    std::vec::Vec<u8> => 24,
+}
+
+pub(crate) struct Internal(u64);
+
+struct Private(u8);
+
+#[repr(u8)]
+pub enum Kind {
+    Empty,
+    Full,
+}
+
+pub struct Wrapper<T>(pub T);
+
+pub mod shapes {
+    pub struct Circle {
+        pub radius: f64,
+    }
+
+    // Asserting this header leaves the one in `net` unasserted.
+    #[assert_size_derive::assert_size(4)]
+    pub struct Header(pub u32);
+}
@@ -0,0 +1,16 @@
+/// The header of every packet.
+#[repr(C)]
+pub struct Header {
+    pub length: u16,
+    pub flags: u8,
+}
+
+pub union Bits {
+    pub word: u32,
+    pub bytes: [u8; 4],
+}
+
+/// A buffer, which `assert_sizes!` on `std::vec::Vec` does not assert.
+pub struct Vec {
+    pub len: u32,
+}
@@ -0,0 +1,689 @@
+//! Parsing of the arguments accepted by the attribute macros.
+
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{ToTokens, quote};
+use syn::{
+    Error, Expr, ExprLit, ExprRange, Ident, Lit, LitStr, Meta, RangeLimits, Result, Token, Type, bracketed,
+    parenthesized, parse::{Parse, ParseStream}, punctuated::Punctuated, spanned::Spanned,
+    token::Paren
+};
+
+use crate::c_header::CStructOption;
+use crate::repr::{PRIMITIVE_REPRS, REPR_HINTS};
+
+/// The instantiations listed in a `for = [...]` option.
+pub(crate) type Instantiations<V> = Punctuated<Instantiation<V>, Token![,]>;
+
+#[derive(Default)]
+pub(crate) struct AssertSizeAttributeArgs {
+    /// The expected size, which is only optional when implied by another option.
+    pub(crate) desired_size_in_bytes: Option<DesiredSize>,
+    pub(crate) desired_align_in_bytes: Option<Expected>,
+    /// A type whose size must match, given with `same_as = Type`.
+    pub(crate) same_size_as: Option<Type>,
+    /// A type whose size and alignment must match, given with `same_layout_as = Type`.
+    pub(crate) same_layout_as: Option<Type>,
+    /// The span of the `option_same_size` flag, if given.
+    pub(crate) option_same_size: Option<Span>,
+    /// How many times larger than the next largest variant the payload of each enum
+    /// variant may be, given with `max_variant_ratio = K`.
+    pub(crate) max_variant_ratio: Option<Expected>,
+    /// The expected discriminant type of an enum, given with `discriminant = u8`.
+    pub(crate) discriminant: Option<Ident>,
+    /// The repr hints the type must have, given with `repr = "C"`.
+    pub(crate) repr: Option<ReprOption>,
+    /// The repr hints the type must not have, given with `forbid_repr = "packed"`.
+    pub(crate) forbid_repr: Option<ReprOption>,
+    /// The C struct whose layout must match, given with `c_header = "foo.h"` and
+    /// `c_struct = "foo_t"`.
+    pub(crate) c_struct: Option<CStructOption>,
+    /// The `c_export` option, which writes a C declaration of the type into the given
+    /// header.
+    pub(crate) c_export: Option<PathOption>,
+    /// The span of the `no_padding` flag, if given.
+    pub(crate) no_padding: Option<Span>,
+    /// The span of the `report` flag, if given.
+    pub(crate) report: Option<Span>,
+    /// The `test` option, which generates a test that checks the layout of the type
+    /// against a snapshot in the given directory.
+    pub(crate) test: Option<PathOption>,
+    /// The `snapshot` option, which checks the size of the type against the given
+    /// lockfile.
+    pub(crate) snapshot: Option<PathOption>,
+    /// The level given with `level = "..."`, if any.
+    pub(crate) level: Option<Level>,
+    pub(crate) instantiations: Option<Instantiations<Expectation>>,
+}
+
+impl AssertSizeAttributeArgs {
+    /// The level at which failed assertions are reported.
+    pub(crate) fn level(&self) -> Level {
+        Level::resolve(self.level)
+    }
+}
+
+/// An option such as `test` that may be followed by a path, as in `test = "dir"`.
+pub(crate) struct PathOption {
+    pub(crate) span: Span,
+    /// The path, relative to the crate's manifest directory.
+    pub(crate) path: Option<LitStr>,
+}
+
+/// A list of repr hints given as a string, as in `repr = "C, align(8)"`. A hint without
+/// arguments, such as `packed`, also matches the same hint with arguments.
+pub(crate) struct ReprOption {
+    pub(crate) span: Span,
+    pub(crate) hints: Vec<Meta>,
+}
+
+/// The types listed in `assert_sizes!`.
+pub(crate) struct AssertSizesArgs {
+    pub(crate) entries: Punctuated<TypeAssertion, Token![,]>,
+}
+
+/// A single `Type => N` or `Type => (N, options...)` entry of `assert_sizes!`.
+pub(crate) struct TypeAssertion {
+    pub(crate) ty: Type,
+    pub(crate) args: AssertSizeAttributeArgs,
+}
+
+pub(crate) struct AssertAlignAttributeArgs {
+    pub(crate) desired_align_in_bytes: Expected,
+    /// The level given with `level = "..."`, if any.
+    pub(crate) level: Option<Level>,
+    pub(crate) instantiations: Option<Instantiations<Expected>>,
+}
+
+impl AssertAlignAttributeArgs {
+    /// The level at which failed assertions are reported.
+    pub(crate) fn level(&self) -> Level {
+        Level::resolve(self.level)
+    }
+}
+
+/// How a failed assertion is reported.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Level {
+    /// A compile error, which is the default.
+    Error,
+    /// A compile warning, given with `level = "warn"` or forced by the `warn` feature.
+    Warn,
+}
+
+impl Level {
+    /// The level of an assertion given `level` in its arguments, which the `warn` feature
+    /// overrides.
+    fn resolve(level: Option<Level>) -> Level {
+        if cfg!(feature = "warn") {
+            Level::Warn
+        } else {
+            level.unwrap_or(Level::Error)
+        }
+    }
+}
+
+impl Parse for Level {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let level: LitStr = input.parse()?;
+        match level.value().as_str() {
+            "error" => Ok(Level::Error),
+            "warn" => Ok(Level::Warn),
+            _ => Err(Error::new(level.span(), "expected `\"error\"` or `\"warn\"`")),
+        }
+    }
+}
+
+/// A concrete instantiation of a generic type listed in `for = [...]`, optionally
+/// overriding the expected value with `=> N`.
+pub(crate) struct Instantiation<V> {
+    pub(crate) ty: Type,
+    pub(crate) desired_value: Option<V>,
+}
+
+/// The expected size of the annotated type, either the same on every target or chosen by
+/// `cfg` predicate.
+pub(crate) enum DesiredSize {
+    Uniform(Expectation),
+    /// Arms such as `target_pointer_width = "64" => 24`, of which the first one matching
+    /// the target applies.
+    PerTarget(Vec<TargetArm>),
+}
+
+pub(crate) struct TargetArm {
+    /// The `cfg` predicate, or `None` for the `_` fallback arm.
+    pub(crate) predicate: Option<TokenStream2>,
+    pub(crate) desired_size_in_bytes: Expectation,
+    pub(crate) span: Span,
+}
+
+/// An expected size: either an exact number of bytes, or one or more bounds written as
+/// `<= N`, `< N`, `>= N`, `> N` or a range such as `16..=32`.
+#[derive(Clone)]
+pub(crate) enum Expectation {
+    Exact(Expected),
+    Bounded(Vec<Bound>),
+}
+
+/// A single bound on a size, such as `<= 64`.
+#[derive(Clone)]
+pub(crate) struct Bound {
+    pub(crate) op: BoundOp,
+    pub(crate) value: Expected,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum BoundOp {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl BoundOp {
+    /// The comparison operator as written in Rust source.
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            BoundOp::Less => "<",
+            BoundOp::LessOrEqual => "<=",
+            BoundOp::Greater => ">",
+            BoundOp::GreaterOrEqual => ">=",
+        }
+    }
+}
+
+/// An expected number of bytes, given as a const expression of type `usize`, along with
+/// the span it was written at so that a mismatch can be reported there.
+#[derive(Clone)]
+pub(crate) struct Expected {
+    pub(crate) expr: Expr,
+    /// The value of `expr` when it is an integer literal, which allows it to be validated
+    /// and quoted in messages as is.
+    pub(crate) literal: Option<usize>,
+    pub(crate) span: Span,
+}
+
+impl Expected {
+    fn from_expr(expr: Expr) -> Result<Self> {
+        let literal = match &expr {
+            Expr::Lit(ExprLit { lit: Lit::Int(lit), .. }) => Some(lit.base10_parse()?),
+            _ => None,
+        };
+        Ok(Expected { span: expr.span(), expr, literal })
+    }
+}
+
+impl Parse for Expected {
+    fn parse(input: ParseStream) -> Result<Self> {
+        Expected::from_expr(input.parse()?)
+    }
+}
+
+impl ToTokens for BoundOp {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        tokens.extend(match self {
+            BoundOp::Less => quote!(<),
+            BoundOp::LessOrEqual => quote!(<=),
+            BoundOp::Greater => quote!(>),
+            BoundOp::GreaterOrEqual => quote!(>=),
+        });
+    }
+}
+
+impl Parse for Expectation {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let comparison = if input.peek(Token![<=]) {
+            input.parse::<Token![<=]>()?;
+            Some(BoundOp::LessOrEqual)
+        } else if input.peek(Token![<]) {
+            input.parse::<Token![<]>()?;
+            Some(BoundOp::Less)
+        } else if input.peek(Token![>=]) {
+            input.parse::<Token![>=]>()?;
+            Some(BoundOp::GreaterOrEqual)
+        } else if input.peek(Token![>]) {
+            input.parse::<Token![>]>()?;
+            Some(BoundOp::Greater)
+        } else {
+            None
+        };
+        if let Some(op) = comparison {
+            return Ok(Expectation::Bounded(vec![Bound { op, value: input.parse()? }]));
+        }
+
+        // A range parses as a single expression, which is split back into its bounds. It
+        // may omit either its start or its end, but not both.
+        let ExprRange { start, limits, end, .. } = match input.parse::<Expr>()? {
+            Expr::Range(range) => range,
+            expr => return Ok(Expectation::Exact(Expected::from_expr(expr)?)),
+        };
+
+        let mut bounds = Vec::new();
+        if let Some(start) = start {
+            bounds.push(Bound { op: BoundOp::GreaterOrEqual, value: Expected::from_expr(*start)? });
+        }
+        if let Some(end) = end {
+            let op = match limits {
+                RangeLimits::HalfOpen(_) => BoundOp::Less,
+                RangeLimits::Closed(_) => BoundOp::LessOrEqual,
+            };
+            bounds.push(Bound { op, value: Expected::from_expr(*end)? });
+        }
+        if bounds.is_empty() {
+            return Err(Error::new_spanned(limits, "ranges must have a start or an end"));
+        }
+
+        Ok(Expectation::Bounded(bounds))
+    }
+}
+
+impl Parse for ReprOption {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let list: LitStr = input.parse()?;
+        let hints = list.parse_with(Punctuated::<Meta, Token![,]>::parse_terminated)?;
+        if hints.is_empty() {
+            return Err(Error::new(list.span(), "expected a repr such as `\"C\"`"));
+        }
+        for hint in &hints {
+            let known = hint
+                .path()
+                .get_ident()
+                .is_some_and(|ident| REPR_HINTS.iter().chain(PRIMITIVE_REPRS).any(|repr| ident == repr));
+            if !known {
+                return Err(Error::new(
+                    list.span(),
+                    format!("unknown repr `{}`", hint.to_token_stream()),
+                ));
+            }
+        }
+        Ok(ReprOption { span: list.span(), hints: hints.into_iter().collect() })
+    }
+}
+
+impl Parse for TargetArm {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let span = input.span();
+        let predicate = parse_predicate(input)?;
+        input.parse::<Token![=>]>()?;
+        let desired_size_in_bytes = input.parse()?;
+        Ok(TargetArm { predicate, desired_size_in_bytes, span })
+    }
+}
+
+impl Parse for DesiredSize {
+    fn parse(input: ParseStream) -> Result<Self> {
+        if !peek_target_arm(input) {
+            return Ok(DesiredSize::Uniform(input.parse()?));
+        }
+
+        let mut arms: Vec<TargetArm> = Vec::new();
+        loop {
+            let arm: TargetArm = input.parse()?;
+            if arms.last().is_some_and(|last| last.predicate.is_none()) {
+                return Err(Error::new(arm.span, "the `_` arm must come last"));
+            }
+            arms.push(arm);
+
+            let ahead = input.fork();
+            if ahead.parse::<Token![,]>().is_err() || !peek_target_arm(&ahead) {
+                return Ok(DesiredSize::PerTarget(arms));
+            }
+            input.parse::<Token![,]>()?;
+        }
+    }
+}
+
+impl Parse for AssertSizeAttributeArgs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let span = input.span();
+
+        // The size may be left out when it is implied by an option such as `same_as`.
+        let desired_size_in_bytes = if input.is_empty() || peek_option(input) {
+            None
+        } else {
+            let desired_size_in_bytes: DesiredSize = input.parse()?;
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+            Some(desired_size_in_bytes)
+        };
+
+        let mut desired_align_in_bytes = None;
+        let mut same_size_as = None;
+        let mut same_layout_as = None;
+        let mut option_same_size = None;
+        let mut max_variant_ratio = None;
+        let mut discriminant = None;
+        let mut repr = None;
+        let mut forbid_repr = None;
+        let mut c_header: Option<LitStr> = None;
+        let mut c_struct: Option<LitStr> = None;
+        let mut c_export = None;
+        let mut no_padding = None;
+        let mut report = None;
+        let mut test = None;
+        let mut snapshot = None;
+        let mut level = None;
+        let mut instantiations = None;
+        while !input.is_empty() {
+            if input.peek(Token![for]) {
+                parse_instantiations(input, &mut instantiations)?;
+            } else {
+                let key: Ident = input.parse()?;
+                match key.to_string().as_str() {
+                    "align" => {
+                        check_duplicate(&key, &desired_align_in_bytes)?;
+                        input.parse::<Token![=]>()?;
+                        desired_align_in_bytes = Some(parse_alignment(input)?);
+                    }
+                    "same_as" => {
+                        check_duplicate(&key, &same_size_as)?;
+                        input.parse::<Token![=]>()?;
+                        same_size_as = Some(input.parse()?);
+                    }
+                    "same_layout_as" => {
+                        check_duplicate(&key, &same_layout_as)?;
+                        input.parse::<Token![=]>()?;
+                        same_layout_as = Some(input.parse()?);
+                    }
+                    "option_same_size" => {
+                        check_duplicate(&key, &option_same_size)?;
+                        option_same_size = Some(key.span());
+                    }
+                    "max_variant_ratio" => {
+                        check_duplicate(&key, &max_variant_ratio)?;
+                        input.parse::<Token![=]>()?;
+                        let ratio: Expected = input.parse()?;
+                        if ratio.literal == Some(0) {
+                            return Err(Error::new(ratio.span, "`max_variant_ratio` must be at least 1"));
+                        }
+                        max_variant_ratio = Some(ratio);
+                    }
+                    "discriminant" => {
+                        check_duplicate(&key, &discriminant)?;
+                        input.parse::<Token![=]>()?;
+                        let ty: Ident = input.parse()?;
+                        if !PRIMITIVE_REPRS.iter().any(|repr| ty == repr) {
+                            return Err(Error::new(ty.span(), "expected a primitive integer type such as `u8`"));
+                        }
+                        discriminant = Some(ty);
+                    }
+                    "repr" => {
+                        check_duplicate(&key, &repr)?;
+                        input.parse::<Token![=]>()?;
+                        repr = Some(input.parse()?);
+                    }
+                    "forbid_repr" => {
+                        check_duplicate(&key, &forbid_repr)?;
+                        input.parse::<Token![=]>()?;
+                        forbid_repr = Some(input.parse()?);
+                    }
+                    "c_header" => {
+                        check_duplicate(&key, &c_header)?;
+                        input.parse::<Token![=]>()?;
+                        c_header = Some(input.parse()?);
+                    }
+                    "c_struct" => {
+                        check_duplicate(&key, &c_struct)?;
+                        input.parse::<Token![=]>()?;
+                        c_struct = Some(input.parse()?);
+                    }
+                    "c_export" => {
+                        check_duplicate(&key, &c_export)?;
+                        c_export = Some(parse_path_option(input, &key)?);
+                    }
+                    "no_padding" => {
+                        check_duplicate(&key, &no_padding)?;
+                        no_padding = Some(key.span());
+                    }
+                    "report" => {
+                        check_duplicate(&key, &report)?;
+                        report = Some(key.span());
+                    }
+                    "test" => {
+                        check_duplicate(&key, &test)?;
+                        test = Some(parse_path_option(input, &key)?);
+                    }
+                    "snapshot" => {
+                        check_duplicate(&key, &snapshot)?;
+                        snapshot = Some(parse_path_option(input, &key)?);
+                    }
+                    "level" => {
+                        check_duplicate(&key, &level)?;
+                        input.parse::<Token![=]>()?;
+                        level = Some(input.parse()?);
+                    }
+                    _ => {
+                        return Err(Error::new(
+                            key.span(),
+                            format!("unknown `assert_size` option `{}`", key),
+                        ));
+                    }
+                }
+            }
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        let c_struct = match (c_header, c_struct) {
+            (Some(header), Some(name)) => Some(CStructOption { header, name }),
+            (Some(header), None) => return Err(Error::new(header.span(), "`c_header` requires `c_struct`")),
+            (None, Some(name)) => return Err(Error::new(name.span(), "`c_struct` requires `c_header`")),
+            (None, None) => None,
+        };
+
+        if desired_size_in_bytes.is_none()
+            && same_size_as.is_none()
+            && same_layout_as.is_none()
+            && snapshot.is_none()
+            && max_variant_ratio.is_none()
+            && discriminant.is_none()
+            && repr.is_none()
+            && forbid_repr.is_none()
+            && c_struct.is_none()
+        {
+            return Err(Error::new(
+                span,
+                "expected the size of the type, or an option such as `same_as = Type` or `snapshot`",
+            ));
+        }
+
+        Ok(AssertSizeAttributeArgs {
+            desired_size_in_bytes,
+            desired_align_in_bytes,
+            same_size_as,
+            same_layout_as,
+            option_same_size,
+            max_variant_ratio,
+            discriminant,
+            repr,
+            forbid_repr,
+            c_struct,
+            c_export,
+            no_padding,
+            report,
+            test,
+            snapshot,
+            level,
+            instantiations,
+        })
+    }
+}
+
+impl Parse for AssertSizesArgs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        Ok(AssertSizesArgs { entries: input.parse_terminated(TypeAssertion::parse, Token![,])? })
+    }
+}
+
+impl Parse for TypeAssertion {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let ty: Type = input.parse()?;
+        input.parse::<Token![=>]>()?;
+
+        // Parentheses hold the same arguments as `#[assert_size(...)]`, unless they are
+        // only the start of an expected size such as `(A + B) * 2`.
+        let ahead = input.fork();
+        let parenthesized = ahead.peek(Paren) && {
+            let _content;
+            parenthesized!(_content in ahead);
+            ahead.is_empty() || ahead.peek(Token![,])
+        };
+        let args = if parenthesized {
+            let content;
+            parenthesized!(content in input);
+            content.parse()?
+        } else {
+            // Without parentheses, target arms would run into the next entry.
+            AssertSizeAttributeArgs {
+                desired_size_in_bytes: Some(DesiredSize::Uniform(input.parse()?)),
+                ..Default::default()
+            }
+        };
+
+        Ok(TypeAssertion { ty, args })
+    }
+}
+
+impl Parse for AssertAlignAttributeArgs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let desired_align_in_bytes = parse_alignment(input)?;
+        if !input.is_empty() {
+            input.parse::<Token![,]>()?;
+        }
+
+        let mut level = None;
+        let mut instantiations = None;
+        while !input.is_empty() {
+            if input.peek(Token![for]) {
+                parse_instantiations(input, &mut instantiations)?;
+            } else {
+                let key: Ident = input.parse()?;
+                if key != "level" {
+                    return Err(Error::new(
+                        key.span(),
+                        format!("unknown `assert_align` option `{}`", key),
+                    ));
+                }
+                check_duplicate(&key, &level)?;
+                input.parse::<Token![=]>()?;
+                level = Some(input.parse()?);
+            }
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(AssertAlignAttributeArgs { desired_align_in_bytes, level, instantiations })
+    }
+}
+
+impl<V: Parse> Parse for Instantiation<V> {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let ty: Type = input.parse()?;
+
+        let desired_value = if input.parse::<Option<Token![=>]>>()?.is_some() {
+            Some(input.parse()?)
+        } else {
+            None
+        };
+
+        Ok(Instantiation { ty, desired_value })
+    }
+}
+
+/// Parses the predicate of a target arm, returning `None` for the `_` fallback arm.
+fn parse_predicate(input: ParseStream) -> Result<Option<TokenStream2>> {
+    if input.parse::<Option<Token![_]>>()?.is_some() {
+        return Ok(None);
+    }
+
+    let key: Ident = input.parse()?;
+    if input.peek(Paren) {
+        // `cfg(...)` is unwrapped, while `all(...)`, `any(...)` and `not(...)` are
+        // predicates in their own right.
+        let content;
+        parenthesized!(content in input);
+        let inner: TokenStream2 = content.parse()?;
+        if key == "cfg" {
+            Ok(Some(inner))
+        } else {
+            Ok(Some(quote!(#key(#inner))))
+        }
+    } else if input.peek(Token![=]) && !input.peek(Token![=>]) {
+        input.parse::<Token![=]>()?;
+        let value: LitStr = input.parse()?;
+        Ok(Some(quote!(#key = #value)))
+    } else {
+        Ok(Some(quote!(#key)))
+    }
+}
+
+/// Checks whether a target arm such as `unix => 8`, `target_pointer_width = "64" => 24`,
+/// `cfg(...) => 8` or `_ => 8` follows, as opposed to an expected size or an option.
+fn peek_target_arm(input: ParseStream) -> bool {
+    let ahead = input.fork();
+    parse_predicate(&ahead).is_ok() && ahead.peek(Token![=>])
+}
+
+/// Checks whether an option such as `align = 8` or `snapshot` follows, as opposed to an
+/// expected size.
+fn peek_option(input: ParseStream) -> bool {
+    let ahead = input.fork();
+    let snapshot = ahead.parse::<Ident>().is_ok_and(|key| key == "snapshot")
+        && (ahead.is_empty() || ahead.peek(Token![,]) || ahead.peek(Token![=]) && !ahead.peek(Token![=>]));
+
+    snapshot
+        || input.peek(Token![for])
+        || input.peek(Ident)
+            && input.peek2(Token![=])
+            && !input.peek2(Token![=>])
+            && !input.peek2(Token![==])
+            && !peek_target_arm(input)
+}
+
+/// Rejects an option that was already given.
+fn check_duplicate<T>(key: &Ident, value: &Option<T>) -> Result<()> {
+    match value {
+        Some(_) => Err(Error::new(key.span(), format!("duplicate `{}` option", key))),
+        None => Ok(()),
+    }
+}
+
+/// Parses the optional path following an option such as `test`.
+fn parse_path_option(input: ParseStream, key: &Ident) -> Result<PathOption> {
+    let path = if input.parse::<Option<Token![=]>>()?.is_some() {
+        Some(input.parse()?)
+    } else {
+        None
+    };
+    Ok(PathOption { span: key.span(), path })
+}
+
+/// Parses a `for = [...]` option into `instantiations`, rejecting duplicates.
+fn parse_instantiations<V: Parse>(
+    input: ParseStream,
+    instantiations: &mut Option<Instantiations<V>>,
+) -> Result<()> {
+    let key = input.parse::<Token![for]>()?;
+    if instantiations.is_some() {
+        return Err(Error::new(key.span, "duplicate `for` option"));
+    }
+    input.parse::<Token![=]>()?;
+
+    let content;
+    bracketed!(content in input);
+    *instantiations = Some(content.parse_terminated(Instantiation::parse, Token![,])?);
+    Ok(())
+}
+
+/// Parses an expected alignment, which must be a power of two if given as a literal.
+fn parse_alignment(input: ParseStream) -> Result<Expected> {
+    let alignment: Expected = input.parse()?;
+    if alignment.literal.is_some_and(|literal| !literal.is_power_of_two()) {
+        return Err(Error::new(alignment.span, "alignment must be a power of two"));
+    }
+    Ok(alignment)
+}
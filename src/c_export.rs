@@ -0,0 +1,338 @@
+//! Generation of C declarations for the `c_export` option.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use syn::{
+    Data, DeriveInput, Error, Expr, ExprLit, Fields, GenericArgument, Lit, PathArguments, Result, ReturnType,
+    Type, ext::IdentExt, spanned::Spanned,
+};
+
+use crate::args::{AssertSizeAttributeArgs, DesiredSize, Expectation, PathOption};
+use crate::repr::has_repr;
+
+/// The header written to `OUT_DIR` by `c_export` unless a path is given.
+const DEFAULT_HEADER: &str = "assert_size.h";
+
+/// How old the lock file of a header must be to be taken over, as left behind by a
+/// compiler that was killed while writing it.
+const STALE_LOCK: Duration = Duration::from_secs(10);
+
+/// A type exported to a header while compiling the current crate.
+struct Exported {
+    header: PathBuf,
+    name: String,
+    /// The location of the type's name in the source, which tells types apart.
+    location: String,
+    block: String,
+}
+
+/// The types exported so far by the procedural macro's process.
+static EXPORTED: Mutex<Vec<Exported>> = Mutex::new(Vec::new());
+
+/// Writes a C declaration of the annotated type, along with `_Static_assert`s of its
+/// expected size and alignment, into the header given by `c_export`.
+///
+/// Every type exported to the same header gets its own block in it, delimited by marker
+/// comments. A block is replaced when its type is exported again and other blocks are kept
+/// as they are, so the header is only written when a declaration changes.
+pub(crate) fn export_c_declaration(
+    input: &DeriveInput,
+    args: &AssertSizeAttributeArgs,
+    export: &PathOption,
+) -> Result<()> {
+    let span = export.span;
+    let name = input.ident.unraw().to_string();
+    if !has_repr(&input.attrs, "C")? {
+        return Err(Error::new(span, format!("`c_export` requires `#[repr(C)]` on `{}`", name)));
+    }
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(&input.generics.params, "`c_export` is not supported on generic types"));
+    }
+    let Some(DesiredSize::Uniform(Expectation::Exact(size))) = &args.desired_size_in_bytes else {
+        return Err(Error::new(span, "`c_export` requires the expected size as an integer literal"));
+    };
+    let size = size
+        .literal
+        .ok_or_else(|| Error::new(size.span, "`c_export` requires the expected size as an integer literal"))?;
+
+    let (keyword, fields) = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => ("struct", &fields.named),
+            _ => return Err(Error::new(span, "`c_export` is only supported on structs with named fields")),
+        },
+        Data::Union(data) => ("union", &data.fields.named),
+        Data::Enum(_) => return Err(Error::new(span, "`c_export` is only supported on structs and unions")),
+    };
+    if fields.is_empty() {
+        return Err(Error::new(span, format!("`{}` has no fields, which C does not allow", name)));
+    }
+
+    // The typedef comes first so that fields can point to the type itself.
+    let mut block = format!("{}\ntypedef {} {} {};\n{} {} {{\n", begin_marker(&name), keyword, name, name, keyword, name);
+    for field in fields {
+        let field_name = field.ident.as_ref().unwrap().unraw().to_string();
+        block += &format!("    {};\n", c_declaration(&field.ty, &field_name, false)?);
+    }
+    block += "};\n";
+    block += &format!("_Static_assert(sizeof({}) == {}, \"{} must be {} bytes\");\n", name, size, name, size);
+    if let Some(align) = args.desired_align_in_bytes.as_ref().and_then(|align| align.literal) {
+        block += &format!(
+            "_Static_assert(_Alignof({}) == {}, \"{} must be aligned to {} bytes\");\n",
+            name, align, name, align
+        );
+    }
+    block += &end_marker(&name);
+
+    let path = match &export.path {
+        Some(path) => {
+            let manifest_dir = std::env::var_os("CARGO_MANIFEST_DIR")
+                .ok_or_else(|| Error::new(span, "`c_export` requires building with Cargo"))?;
+            PathBuf::from(manifest_dir).join(path.value())
+        }
+        None => {
+            let out_dir = std::env::var_os("OUT_DIR").ok_or_else(|| {
+                Error::new(
+                    span,
+                    "`c_export` writes to `OUT_DIR`, which is only set for crates with a build script; give a path such as `c_export = \"include/types.h\"` instead",
+                )
+            })?;
+            PathBuf::from(out_dir).join(DEFAULT_HEADER)
+        }
+    };
+    // C types share a single namespace, so a type with the same name in another module
+    // would silently replace the block. The location of the name tells the types apart,
+    // and a different block tells them apart from the same type after an edit.
+    let location = format!("{:?}", input.ident.span());
+    {
+        let mut exported = EXPORTED.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        match exported.iter_mut().find(|exported| exported.header == path && exported.name == name) {
+            Some(exported) if exported.location != location && exported.block != block => {
+                return Err(Error::new(
+                    span,
+                    format!("another type named `{}` is already exported to this header; rename one of them", name),
+                ));
+            }
+            Some(exported) => {
+                exported.location = location;
+                exported.block = block.clone();
+            }
+            None => exported.push(Exported { header: path.clone(), name: name.clone(), location, block: block.clone() }),
+        }
+    }
+
+    update_header(&path, &name, &block)
+        .map_err(|err| Error::new(span, format!("failed to write `{}`: {}", path.display(), err)))
+}
+
+/// Declares `declarator` with the C equivalent of `ty`, as in `uint8_t name[16]` for a
+/// declarator `name` of type `[u8; 16]`. An empty declarator gives the type name alone.
+fn c_declaration(ty: &Type, declarator: &str, is_const: bool) -> Result<String> {
+    let qualifier = if is_const { "const " } else { "" };
+    match ty {
+        Type::Paren(paren) => c_declaration(&paren.elem, declarator, is_const),
+        Type::Group(group) => c_declaration(&group.elem, declarator, is_const),
+        Type::Array(array) => {
+            let Expr::Lit(ExprLit { lit: Lit::Int(len), .. }) = &array.len else {
+                return Err(Error::new_spanned(&array.len, "array lengths must be integer literals for `c_export`"));
+            };
+            // Without parentheses, `*name[4]` would declare an array of pointers.
+            let declarator = if declarator.starts_with('*') {
+                format!("({})[{}]", declarator, len.base10_digits())
+            } else {
+                format!("{}[{}]", declarator, len.base10_digits())
+            };
+            c_declaration(&array.elem, &declarator, is_const)
+        }
+        Type::Ptr(pointer) => {
+            c_declaration(&pointer.elem, &format!("*{}{}", qualifier, declarator), pointer.const_token.is_some())
+        }
+        Type::Reference(reference) => c_declaration(
+            &reference.elem,
+            &format!("*{}{}", qualifier, declarator),
+            reference.mutability.is_none(),
+        ),
+        Type::BareFn(function) => {
+            let params = function
+                .inputs
+                .iter()
+                .map(|param| c_declaration(&param.ty, "", false))
+                .collect::<Result<Vec<_>>>()?;
+            let params = if params.is_empty() { "void".to_owned() } else { params.join(", ") };
+            let declarator = format!("(*{}{})({})", qualifier, declarator, params);
+            match &function.output {
+                ReturnType::Type(_, output) if !matches!(&**output, Type::Tuple(tuple) if tuple.elems.is_empty()) => {
+                    c_declaration(output, &declarator, false)
+                }
+                _ => Ok(format!("void {}", declarator)),
+            }
+        }
+        Type::Path(path) if path.qself.is_none() => {
+            let segment = path.path.segments.last().unwrap();
+            let ident = segment.ident.to_string();
+            // `Option` of a pointer that cannot be null, and `NonNull`, are plain pointers.
+            if let PathArguments::AngleBracketed(generics) = &segment.arguments {
+                if let (Some(GenericArgument::Type(inner)), 1) = (generics.args.first(), generics.args.len()) {
+                    match ident.as_str() {
+                        "Option" if matches!(inner, Type::BareFn(_) | Type::Reference(_)) || is_non_null(inner) => {
+                            return c_declaration(inner, declarator, is_const);
+                        }
+                        "NonNull" => return c_declaration(inner, &format!("*{}{}", qualifier, declarator), false),
+                        _ => {}
+                    }
+                }
+                return Err(no_c_equivalent(ty));
+            }
+            let name = match ident.as_str() {
+                "u8" => "uint8_t",
+                "u16" => "uint16_t",
+                "u32" | "char" => "uint32_t",
+                "u64" => "uint64_t",
+                "i8" => "int8_t",
+                "i16" => "int16_t",
+                "i32" => "int32_t",
+                "i64" => "int64_t",
+                "usize" => "uintptr_t",
+                "isize" => "intptr_t",
+                "f32" | "c_float" => "float",
+                "f64" | "c_double" => "double",
+                "bool" => "bool",
+                "c_void" => "void",
+                "c_char" => "char",
+                "c_schar" => "signed char",
+                "c_uchar" => "unsigned char",
+                "c_short" => "short",
+                "c_ushort" => "unsigned short",
+                "c_int" => "int",
+                "c_uint" => "unsigned int",
+                "c_long" => "long",
+                "c_ulong" => "unsigned long",
+                "c_longlong" => "long long",
+                "c_ulonglong" => "unsigned long long",
+                "u128" | "i128" | "PhantomData" | "PhantomPinned" | "String" | "Vec" | "Box" => {
+                    return Err(no_c_equivalent(ty));
+                }
+                // Any other type is assumed to be exported as well.
+                name => name,
+            };
+            Ok(format!("{}{} {}", qualifier, name, declarator).trim_end().to_owned())
+        }
+        _ => Err(no_c_equivalent(ty)),
+    }
+}
+
+fn is_non_null(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path) if path.path.segments.last().is_some_and(|segment| segment.ident == "NonNull"))
+}
+
+fn no_c_equivalent(ty: &Type) -> Error {
+    Error::new(ty.span(), "this type has no C equivalent for `c_export`")
+}
+
+/// A lock file held while a header is updated, which is removed when dropped.
+struct HeaderLock(PathBuf);
+
+impl HeaderLock {
+    /// Creates the lock file at `path`, waiting for other processes holding it.
+    fn acquire(path: &Path) -> io::Result<Self> {
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(path) {
+                Ok(_) => return Ok(HeaderLock(path.to_owned())),
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+                    let age = fs::metadata(path).and_then(|metadata| metadata.modified()).ok().and_then(|time| time.elapsed().ok());
+                    if age.is_some_and(|age| age > STALE_LOCK) {
+                        let _ = fs::remove_file(path);
+                    } else {
+                        thread::sleep(Duration::from_millis(10));
+                    }
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+impl Drop for HeaderLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+fn begin_marker(name: &str) -> String {
+    format!("/* begin {} */", name)
+}
+
+fn end_marker(name: &str) -> String {
+    format!("/* end {} */", name)
+}
+
+/// Replaces or appends the block of the type `name` in the header at `path`, writing the
+/// header only if it changes.
+///
+/// The header is written to a temporary file next to it and renamed into place, so a C
+/// build reading it while the crate compiles never sees it half written, and it is locked
+/// while it is read and written, so crates compiled in parallel keep each other's blocks.
+/// Blocks of types that are no longer exported are kept, since other crates may export to
+/// the same header; delete the header and rebuild to remove them.
+fn update_header(path: &Path, name: &str, block: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let file_name = path.file_name().map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+    let _lock = HeaderLock::acquire(&path.with_file_name(format!(".{}.lock", file_name)))?;
+    let existing = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err),
+    };
+
+    // The blocks of the existing header, in order, with their names.
+    let mut blocks: Vec<(String, String)> = Vec::new();
+    let mut lines = existing.lines();
+    while let Some(line) = lines.next() {
+        let Some(block_name) = line.strip_prefix("/* begin ").and_then(|rest| rest.strip_suffix(" */")) else {
+            continue;
+        };
+        let end = end_marker(block_name);
+        let mut text = line.to_owned();
+        for line in lines.by_ref() {
+            text += "\n";
+            text += line;
+            if line == end {
+                break;
+            }
+        }
+        blocks.push((block_name.to_owned(), text));
+    }
+    match blocks.iter_mut().find(|(block_name, _)| block_name == name) {
+        Some((_, text)) => *text = block.to_owned(),
+        None => blocks.push((name.to_owned(), block.to_owned())),
+    }
+
+    let guard: String = file_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect();
+    let mut text = format!(
+        "/* C declarations of the types exported by `#[assert_size(c_export)]`. Generated; do not edit. */\n\
+         #ifndef {guard}\n#define {guard}\n\n#include <stdbool.h>\n#include <stddef.h>\n#include <stdint.h>\n\n"
+    );
+    for (_, block) in &blocks {
+        text += block;
+        text += "\n\n";
+    }
+    text += &format!("#endif /* {} */\n", guard);
+
+    if text != existing {
+        let temp = path.with_file_name(format!(".{}.{}.tmp", file_name, std::process::id()));
+        if let Err(err) = fs::write(&temp, text).and_then(|()| fs::rename(&temp, path)) {
+            let _ = fs::remove_file(&temp);
+            return Err(err);
+        }
+    }
+    Ok(())
+}
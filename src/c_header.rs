@@ -0,0 +1,947 @@
+//! Code generation for the `c_header` option, along with the parsing of C headers and the
+//! computation of C struct layouts it needs.
+//!
+//! Only the subset of C found in FFI headers is understood: struct, union, enum and
+//! typedef declarations of scalar, pointer, array and record types. Other declarations
+//! and preprocessor directives are skipped, except object-like `#define`s, which may be
+//! used in array lengths. Values that a checked struct does not use are never evaluated,
+//! so the rest of the header may hold any C.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, quote_spanned};
+use syn::{Data, DeriveInput, Error, Fields, LitStr, Result, ext::IdentExt};
+
+use crate::args::Level;
+use crate::expand::{AssertedType, Diagnostic, combine_errors, equality_check};
+
+/// The C struct named by the `c_header` and `c_struct` options.
+pub(crate) struct CStructOption {
+    /// The header, relative to the crate's manifest directory.
+    pub(crate) header: LitStr,
+    /// The name of the struct, either a typedef name or a tag such as `struct foo`.
+    pub(crate) name: LitStr,
+}
+
+/// The rules that C layouts differ by between targets, along with the `cfg` predicate of
+/// the targets that follow them.
+struct Abi {
+    predicate: &'static str,
+    pointer: usize,
+    long: usize,
+    /// The alignment of `long long` and `double`, which is only 4 on 32-bit x86 outside
+    /// of Windows.
+    wide_align: usize,
+}
+
+/// The ABIs that cover the common 32-bit and 64-bit targets.
+const ABIS: &[Abi] = &[
+    // LP64, as on 64-bit Unix.
+    Abi { predicate: r#"all(target_pointer_width = "64", not(windows))"#, pointer: 8, long: 8, wide_align: 8 },
+    // LLP64, as on 64-bit Windows.
+    Abi { predicate: r#"all(target_pointer_width = "64", windows)"#, pointer: 8, long: 4, wide_align: 8 },
+    // The i386 System V ABI.
+    Abi {
+        predicate: r#"all(target_pointer_width = "32", target_arch = "x86", not(windows))"#,
+        pointer: 4,
+        long: 4,
+        wide_align: 4,
+    },
+    // ILP32 with natural alignment, as on 32-bit ARM, WebAssembly and 32-bit Windows.
+    Abi {
+        predicate: r#"all(target_pointer_width = "32", not(all(target_arch = "x86", not(windows))))"#,
+        pointer: 4,
+        long: 4,
+        wide_align: 8,
+    },
+];
+
+/// Generates the assertions that each of `types` has the size and field offsets of the
+/// C struct named by `c_struct`, for the ABI of each supported target.
+pub(crate) fn c_header_assertions<V>(
+    input: &DeriveInput,
+    types: &[AssertedType<V>],
+    c_struct: &CStructOption,
+    level: Level,
+) -> Result<TokenStream2> {
+    let CStructOption { header: header_path, name } = c_struct;
+    let struct_name = name.value();
+    let relative_path = header_path.value();
+    let manifest_dir = std::env::var_os("CARGO_MANIFEST_DIR")
+        .ok_or_else(|| Error::new(header_path.span(), "`c_header` requires building with Cargo"))?;
+    let path = PathBuf::from(manifest_dir).join(&relative_path);
+    let path_str = path.display().to_string();
+
+    let text = fs::read_to_string(&path)
+        .map_err(|err| Error::new(header_path.span(), format!("failed to read `{}`: {}", relative_path, err)))?;
+    let header = Header::parse(&text)
+        .map_err(|err| Error::new(header_path.span(), format!("failed to parse `{}`: {}", relative_path, err)))?;
+    let record = header.record(&struct_name).ok_or_else(|| {
+        Error::new(name.span(), format!("`{}` does not define `{}`", relative_path, struct_name))
+    })?;
+    let c_fields = record
+        .fields
+        .as_ref()
+        .map_err(|err| Error::new(name.span(), format!("failed to parse `{}`: {}", struct_name, err)))?;
+
+    let rust_fields = match &input.data {
+        Data::Struct(_) if record.is_union => {
+            return Err(Error::new(name.span(), format!("`{}` is a union, but `{}` is a struct", struct_name, input.ident)));
+        }
+        Data::Union(_) if !record.is_union => {
+            return Err(Error::new(name.span(), format!("`{}` is a struct, but `{}` is a union", struct_name, input.ident)));
+        }
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => fields.named.iter().collect::<Vec<_>>(),
+            _ => return Err(Error::new(name.span(), "`c_header` is only supported on structs with named fields")),
+        },
+        Data::Union(data) => data.fields.named.iter().collect(),
+        Data::Enum(_) => return Err(Error::new(name.span(), "`c_header` is only supported on structs and unions")),
+    };
+
+    // Every field must have a counterpart on the other side, by name.
+    let mut errors = Vec::new();
+    for c_field in c_fields {
+        if !rust_fields.iter().any(|field| field.ident.as_ref().unwrap().unraw() == c_field.name) {
+            errors.push(Error::new(
+                name.span(),
+                format!("field `{}` of `{}` is missing from `{}`", c_field.name, struct_name, input.ident),
+            ));
+        }
+    }
+    for field in &rust_fields {
+        let ident = field.ident.as_ref().unwrap();
+        if !c_fields.iter().any(|c_field| ident.unraw() == c_field.name) {
+            errors.push(Error::new(ident.span(), format!("field `{}` is not in `{}`", ident.unraw(), struct_name)));
+        }
+    }
+    combine_errors(errors)?;
+
+    let mut predicates = Vec::new();
+    let mut assertions = TokenStream2::new();
+    for abi in ABIS {
+        let layout = abi
+            .record_layout(record)
+            .map_err(|err| Error::new(name.span(), format!("failed to lay out `{}`: {}", struct_name, err)))?;
+        let predicate: TokenStream2 = abi.predicate.parse().unwrap();
+
+        let mut checks = TokenStream2::new();
+        for asserted in types {
+            let ty = &asserted.ty;
+            let size = layout.size;
+            checks.extend(equality_check(
+                ty,
+                quote!(#size),
+                quote!(::core::mem::size_of::<#ty>()),
+                &Diagnostic {
+                    message: &format!(
+                        "size of `{{Self}}` is {{ACTUAL}} bytes, but `{}` in `{}` is {{EXPECTED}} bytes",
+                        struct_name, relative_path
+                    ),
+                    label: "expected {EXPECTED} bytes, found {ACTUAL} bytes",
+                    warning: "SizeOf",
+                },
+                asserted.span.unwrap_or(name.span()),
+                level,
+            ));
+
+            for (c_field, offset) in c_fields.iter().zip(&layout.offsets) {
+                let ident = rust_fields
+                    .iter()
+                    .filter_map(|field| field.ident.as_ref())
+                    .find(|ident| ident.unraw() == c_field.name)
+                    .unwrap();
+                checks.extend(equality_check(
+                    ty,
+                    quote!(#offset),
+                    quote!(::core::mem::offset_of!(#ty, #ident)),
+                    &Diagnostic {
+                        message: &format!(
+                            "offset of field `{}` in `{{Self}}` is {{ACTUAL}} bytes, but it is {{EXPECTED}} bytes in `{}`",
+                            c_field.name, struct_name
+                        ),
+                        label: "expected {EXPECTED} bytes, found {ACTUAL} bytes",
+                        warning: "OffsetOf",
+                    },
+                    asserted.span.unwrap_or(ident.span()),
+                    level,
+                ));
+            }
+        }
+
+        assertions.extend(quote! {
+            #[cfg(#predicate)]
+            const _: () = {
+                #checks
+            };
+        });
+        predicates.push(predicate);
+    }
+
+    let span = header_path.span();
+    let message = format!("`c_header` does not know the C ABI of the current target to check `{}`", struct_name);
+    // Files read by a procedural macro are not tracked by the compiler, so the header is
+    // also read by the generated code to rebuild it on changes.
+    let tracked_header = quote_spanned! {span=>
+        const _: &[u8] = ::core::include_bytes!(#path_str);
+
+        #[cfg(not(any(#(#predicates),*)))]
+        ::core::compile_error!(#message);
+    };
+    Ok(quote!(#tracked_header #assertions))
+}
+
+/// A C type, with the sizes of its scalars left to the [`Abi`].
+#[derive(Clone)]
+enum CType {
+    Scalar(Scalar),
+    Pointer,
+    Void,
+    Array(Box<CType>, usize),
+    Record(Rc<Record>),
+    /// A struct or union that is declared but not yet defined, or a name that is not
+    /// known, which can only be pointed to.
+    Incomplete(String),
+}
+
+impl CType {
+    /// The name of the incomplete type that `self` holds by value, if any.
+    fn incomplete(&self) -> Option<&str> {
+        match self {
+            CType::Array(element, _) => element.incomplete(),
+            CType::Incomplete(name) => Some(name),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Scalar {
+    Char,
+    Short,
+    Int,
+    Long,
+    LongLong,
+    Float,
+    Double,
+    Bool,
+}
+
+/// A struct or union definition. Its fields are only parsed successfully if all of their
+/// types are understood, which is only an error if the record is checked.
+struct Record {
+    is_union: bool,
+    fields: std::result::Result<Vec<CField>, String>,
+}
+
+struct CField {
+    name: String,
+    ty: CType,
+}
+
+/// The layout of a struct or union on a given ABI.
+struct RecordLayout {
+    size: usize,
+    align: usize,
+    /// The offset of each field, in declaration order.
+    offsets: Vec<usize>,
+}
+
+impl Abi {
+    /// The size and alignment of `ty`.
+    fn layout(&self, ty: &CType) -> std::result::Result<(usize, usize), String> {
+        Ok(match ty {
+            CType::Scalar(scalar) => match scalar {
+                Scalar::Char | Scalar::Bool => (1, 1),
+                Scalar::Short => (2, 2),
+                Scalar::Int | Scalar::Float => (4, 4),
+                Scalar::Long => (self.long, self.long),
+                Scalar::LongLong | Scalar::Double => (8, self.wide_align),
+            },
+            CType::Pointer => (self.pointer, self.pointer),
+            CType::Void => return Err("`void` has no size".to_owned()),
+            CType::Array(element, len) => {
+                let (size, align) = self.layout(element)?;
+                (size * len, align)
+            }
+            CType::Record(record) => {
+                let layout = self.record_layout(record)?;
+                (layout.size, layout.align)
+            }
+            CType::Incomplete(name) => return Err(format!("`{}` has no size", name)),
+        })
+    }
+
+    /// Lays out the fields of a struct one after another at their alignment, or of a union
+    /// all at offset 0, with the size rounded up to the largest alignment.
+    fn record_layout(&self, record: &Record) -> std::result::Result<RecordLayout, String> {
+        let fields = record.fields.as_ref().map_err(Clone::clone)?;
+        let (mut size, mut align) = (0, 1);
+        let mut offsets = Vec::new();
+        for field in fields {
+            let (field_size, field_align) = self.layout(&field.ty)?;
+            align = align.max(field_align);
+            if record.is_union {
+                offsets.push(0);
+                size = size.max(field_size);
+            } else {
+                let offset = size.next_multiple_of(field_align);
+                offsets.push(offset);
+                size = offset + field_size;
+            }
+        }
+        Ok(RecordLayout { size: size.next_multiple_of(align), align, offsets })
+    }
+}
+
+/// The declarations of a header that `c_struct` can name.
+#[derive(Default)]
+struct Header {
+    /// Struct and union definitions, keyed by tag as in `struct foo`.
+    records: HashMap<String, Rc<Record>>,
+    typedefs: HashMap<String, CType>,
+    /// The bodies of the object-like macros defined with `#define`, without comments.
+    defines: HashMap<String, String>,
+}
+
+impl Header {
+    fn parse(text: &str) -> std::result::Result<Self, String> {
+        let mut header = Header::default();
+        let tokens = tokenize(text, &mut header.defines)?;
+        Parser { tokens: &tokens, pos: 0, header: &mut header }.parse_header();
+        Ok(header)
+    }
+
+    /// Finds the struct or union called `name`, which is either a typedef name or a tag
+    /// such as `struct foo`, or a bare tag.
+    fn record(&self, name: &str) -> Option<&Rc<Record>> {
+        if let Some(record) = self.records.get(name) {
+            return Some(record);
+        }
+        match self.typedefs.get(name) {
+            Some(CType::Record(record)) => Some(record),
+            Some(CType::Incomplete(tag)) => self.records.get(tag),
+            _ => self
+                .records
+                .get(&format!("struct {}", name))
+                .or_else(|| self.records.get(&format!("union {}", name))),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    /// A preprocessing number, which is only evaluated if it is used, since it may be a
+    /// floating-point literal such as `1.5f`.
+    Number(String),
+    Punct(char),
+    Str,
+}
+
+/// Splits a header into tokens along with their line numbers, dropping comments and
+/// preprocessor directives. The bodies of object-like `#define`s are added to `defines`.
+fn tokenize(text: &str, defines: &mut HashMap<String, String>) -> std::result::Result<Vec<(Token, usize)>, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let (mut i, mut line) = (0, 1);
+    // Whether only whitespace precedes `i` on its line, where a `#` starts a directive.
+    let mut line_start = true;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\n' {
+            line += 1;
+            line_start = true;
+            i += 1;
+        } else if c.is_whitespace() {
+            i += 1;
+        } else if text_at(&chars, i, "//") {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if text_at(&chars, i, "/*") {
+            i += 2;
+            while i < chars.len() && !text_at(&chars, i, "*/") {
+                line += usize::from(chars[i] == '\n');
+                i += 1;
+            }
+            i += 2;
+        } else if c == '#' && line_start {
+            // Directives continue over lines ending in a backslash, and comments in them
+            // are dropped like elsewhere.
+            let mut directive = String::new();
+            i += 1;
+            while i < chars.len() && chars[i] != '\n' {
+                if text_at(&chars, i, "\\\n") || text_at(&chars, i, "\\\r\n") {
+                    i += if chars[i + 1] == '\r' { 3 } else { 2 };
+                    line += 1;
+                    directive.push(' ');
+                } else if text_at(&chars, i, "//") {
+                    while i < chars.len() && chars[i] != '\n' {
+                        i += 1;
+                    }
+                } else if text_at(&chars, i, "/*") {
+                    i += 2;
+                    while i < chars.len() && !text_at(&chars, i, "*/") {
+                        line += usize::from(chars[i] == '\n');
+                        i += 1;
+                    }
+                    i += 2;
+                    directive.push(' ');
+                } else {
+                    directive.push(chars[i]);
+                    i += 1;
+                }
+            }
+            let mut words = directive.split_whitespace();
+            match words.next() {
+                Some("define") => {
+                    let rest = directive.trim_start()["define".len()..].trim_start();
+                    let name_len = rest.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len());
+                    let (name, body) = rest.split_at(name_len);
+                    // Function-like macros cannot be expanded without their arguments.
+                    if !name.is_empty() && !body.starts_with('(') {
+                        defines.insert(name.to_owned(), body.trim().to_owned());
+                    }
+                }
+                Some("pragma") if words.next() == Some("pack") => {
+                    return Err(format!("line {}: `#pragma pack` is not supported", line));
+                }
+                _ => {}
+            }
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push((Token::Ident(chars[start..i].iter().collect()), line));
+            line_start = false;
+        } else if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(char::is_ascii_digit)) {
+            // A preprocessing number, as in `16`, `0x1Fu`, `1.5f` or `1e-3`.
+            let start = i;
+            while i < chars.len() {
+                if matches!(chars[i], 'e' | 'E' | 'p' | 'P') && matches!(chars.get(i + 1), Some('+' | '-')) {
+                    i += 2;
+                } else if chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '.' {
+                    i += 1;
+                } else {
+                    break;
+                }
+            }
+            tokens.push((Token::Number(chars[start..i].iter().collect()), line));
+            line_start = false;
+        } else if c == '"' || c == '\'' {
+            i += 1;
+            while i < chars.len() && chars[i] != c {
+                i += if chars[i] == '\\' { 2 } else { 1 };
+            }
+            i += 1;
+            tokens.push((Token::Str, line));
+            line_start = false;
+        } else {
+            tokens.push((Token::Punct(c), line));
+            line_start = false;
+            i += 1;
+        }
+    }
+    Ok(tokens)
+}
+
+fn text_at(chars: &[char], i: usize, text: &str) -> bool {
+    text.chars().enumerate().all(|(offset, c)| chars.get(i + offset) == Some(&c))
+}
+
+/// Parses a decimal, hexadecimal or octal integer literal with an optional suffix such as
+/// `u` or `UL`.
+fn parse_number(literal: &str) -> Option<usize> {
+    let digits = literal.trim_end_matches(['u', 'U', 'l', 'L']);
+    if let Some(hex) = digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        usize::from_str_radix(hex, 16).ok()
+    } else if digits.len() > 1 && digits.starts_with('0') {
+        usize::from_str_radix(&digits[1..], 8).ok()
+    } else {
+        digits.parse().ok()
+    }
+}
+
+/// How deeply `#define`s may refer to each other, which stops recursive macros.
+const MAX_EXPANSION_DEPTH: usize = 32;
+
+/// Replaces the names of `#define`d macros in `tokens` with their bodies.
+fn expand(tokens: &[Token], defines: &HashMap<String, String>, depth: usize) -> std::result::Result<Vec<Token>, String> {
+    let mut expanded = Vec::new();
+    for token in tokens {
+        match token {
+            Token::Ident(name) => {
+                let body = defines.get(name).ok_or_else(|| format!("`{}` is not `#define`d", name))?;
+                if depth == MAX_EXPANSION_DEPTH {
+                    return Err(format!("`{}` expands recursively", name));
+                }
+                let body: Vec<Token> = tokenize(body, &mut HashMap::new())?.into_iter().map(|(token, _)| token).collect();
+                expanded.extend(expand(&body, defines, depth + 1)?);
+            }
+            token => expanded.push(token.clone()),
+        }
+    }
+    Ok(expanded)
+}
+
+/// An integer constant expression made of literals, parentheses and the arithmetic and
+/// bitwise operators, as found in array lengths.
+struct Expression<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+}
+
+impl Expression<'_> {
+    fn evaluate(&mut self) -> std::result::Result<usize, String> {
+        let value = self.binary(0)?;
+        match self.tokens.get(self.pos) {
+            None => Ok(value),
+            Some(_) => Err("expected an operator".to_owned()),
+        }
+    }
+
+    /// Evaluates the operators binding at least as tightly as the level `min`, from the
+    /// loosest, `|`, to the tightest, `*`.
+    fn binary(&mut self, min: usize) -> std::result::Result<usize, String> {
+        const LEVELS: &[&[&str]] = &[&["|"], &["^"], &["&"], &["<<", ">>"], &["+", "-"], &["*", "/", "%"]];
+        if min == LEVELS.len() {
+            return self.operand();
+        }
+        let mut value = self.binary(min + 1)?;
+        while let Some(&operator) = LEVELS[min].iter().find(|operator| self.at(operator)) {
+            self.pos += operator.len();
+            let rhs = self.binary(min + 1)?;
+            let result = match operator {
+                "|" => Some(value | rhs),
+                "^" => Some(value ^ rhs),
+                "&" => Some(value & rhs),
+                "<<" => u32::try_from(rhs).ok().and_then(|rhs| value.checked_shl(rhs)),
+                ">>" => u32::try_from(rhs).ok().and_then(|rhs| value.checked_shr(rhs)),
+                "+" => value.checked_add(rhs),
+                "-" => value.checked_sub(rhs),
+                "*" => value.checked_mul(rhs),
+                "/" => value.checked_div(rhs),
+                _ => value.checked_rem(rhs),
+            };
+            value = result.ok_or_else(|| format!("`{} {} {}` overflows", value, operator, rhs))?;
+        }
+        Ok(value)
+    }
+
+    fn operand(&mut self) -> std::result::Result<usize, String> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Number(literal)) => {
+                self.pos += 1;
+                parse_number(literal).ok_or_else(|| format!("`{}` is not an integer", literal))
+            }
+            Some(Token::Punct('(')) => {
+                self.pos += 1;
+                let value = self.binary(0)?;
+                if !self.at(")") {
+                    return Err("expected `)`".to_owned());
+                }
+                self.pos += 1;
+                Ok(value)
+            }
+            Some(Token::Punct('+')) => {
+                self.pos += 1;
+                self.operand()
+            }
+            _ => Err("expected an integer".to_owned()),
+        }
+    }
+
+    /// Whether the operator `text` comes next, as one punctuation token per character.
+    fn at(&self, text: &str) -> bool {
+        text.chars().enumerate().all(|(offset, c)| self.tokens.get(self.pos + offset) == Some(&Token::Punct(c)))
+    }
+}
+
+struct Parser<'a> {
+    tokens: &'a [(Token, usize)],
+    pos: usize,
+    header: &'a mut Header,
+}
+
+type ParseResult<T> = std::result::Result<T, String>;
+
+impl Parser<'_> {
+    /// Parses the top-level declarations, skipping those that are not understood.
+    fn parse_header(&mut self) {
+        while self.pos < self.tokens.len() {
+            let start = self.pos;
+            match self.peek() {
+                Some(Token::Punct(';' | '}')) => self.pos += 1,
+                // `extern "C" {` in headers shared with C++.
+                Some(Token::Ident(ident)) if ident == "extern" && self.peek_at(1) == Some(&Token::Str) => {
+                    self.pos += 2;
+                    self.eat_punct('{');
+                }
+                Some(Token::Ident(ident)) if matches!(ident.as_str(), "typedef" | "struct" | "union" | "enum") => {
+                    if self.parse_declaration().is_err() {
+                        self.pos = start;
+                        self.skip_statement();
+                    }
+                }
+                _ => self.skip_statement(),
+            }
+        }
+    }
+
+    /// Parses a typedef, or a declaration starting with a struct, union or enum type,
+    /// which registers its definition if it has one.
+    fn parse_declaration(&mut self) -> ParseResult<()> {
+        let typedef = self.eat_ident("typedef");
+        let base = self.parse_specifiers()?;
+        if !self.eat_punct(';') {
+            loop {
+                let (name, ty) = self.parse_declarator(&base)?;
+                if typedef {
+                    self.header.typedefs.insert(name, ty);
+                }
+                if !self.eat_punct(',') {
+                    break;
+                }
+            }
+            self.expect_punct(';')?;
+        }
+        Ok(())
+    }
+
+    /// Parses the fields of a struct or union up to the token at `end`.
+    fn parse_fields(&mut self, end: usize) -> ParseResult<Vec<CField>> {
+        let mut fields = Vec::new();
+        while self.pos < end {
+            let base = self.parse_specifiers()?;
+            loop {
+                let line = self.line();
+                let (name, ty) = self.parse_declarator(&base)?;
+                if matches!(ty, CType::Void) {
+                    return Err(format!("line {}: field `{}` has type `void`", line, name));
+                }
+                let ty = self.complete(ty);
+                if let Some(incomplete) = ty.incomplete() {
+                    return Err(format!("line {}: field `{}` has unknown or incomplete type `{}`", line, name, incomplete));
+                }
+                fields.push(CField { name, ty });
+                if !self.eat_punct(',') {
+                    break;
+                }
+            }
+            self.expect_punct(';')?;
+        }
+        Ok(fields)
+    }
+
+    /// Parses the type specifiers and qualifiers of a declaration, such as
+    /// `const unsigned long`, `struct foo` or a typedef name.
+    fn parse_specifiers(&mut self) -> ParseResult<CType> {
+        let line = self.line();
+        let mut base = None;
+        let (mut longs, mut short, mut char, mut int_words) = (0, false, false, false);
+        let (mut float, mut double) = (false, false);
+        while let Some(Token::Ident(ident)) = self.peek() {
+            let ident = ident.clone();
+            match ident.as_str() {
+                "const" | "volatile" | "restrict" | "static" | "extern" | "register" | "inline" => {}
+                "struct" | "union" => {
+                    base = Some(self.parse_record()?);
+                    continue;
+                }
+                "enum" => {
+                    self.pos += 1;
+                    if let Some(Token::Ident(_)) = self.peek() {
+                        self.pos += 1;
+                    }
+                    if self.peek() == Some(&Token::Punct('{')) {
+                        self.pos = self.matching_brace(self.pos)? + 1;
+                    }
+                    base = Some(CType::Scalar(Scalar::Int));
+                    continue;
+                }
+                "signed" | "unsigned" | "int" => int_words = true,
+                "short" => short = true,
+                "long" => longs += 1,
+                "char" => char = true,
+                "float" => float = true,
+                "double" => double = true,
+                "_Bool" | "bool" => base = Some(CType::Scalar(Scalar::Bool)),
+                "void" => base = Some(CType::Void),
+                _ if base.is_some() || longs > 0 || short || char || int_words || float || double => break,
+                _ => base = Some(self.named_type(&ident)),
+            }
+            self.pos += 1;
+        }
+
+        if double && longs > 0 {
+            return Err(format!("line {}: `long double` is not supported", line));
+        }
+        let scalar = if char {
+            Scalar::Char
+        } else if short {
+            Scalar::Short
+        } else if longs == 1 {
+            Scalar::Long
+        } else if longs > 1 {
+            Scalar::LongLong
+        } else if float {
+            Scalar::Float
+        } else if double {
+            Scalar::Double
+        } else if int_words {
+            Scalar::Int
+        } else {
+            return base.ok_or_else(|| format!("line {}: expected a type", line));
+        };
+        Ok(CType::Scalar(scalar))
+    }
+
+    /// Replaces an incomplete struct or union with its definition if it has since been
+    /// defined, as with a typedef declared before the struct it names.
+    fn complete(&self, ty: CType) -> CType {
+        match ty {
+            CType::Array(element, len) => CType::Array(Box::new(self.complete(*element)), len),
+            CType::Incomplete(tag) => match self.header.records.get(&tag) {
+                Some(record) => CType::Record(record.clone()),
+                None => CType::Incomplete(tag),
+            },
+            ty => ty,
+        }
+    }
+
+    /// Resolves a typedef name, including those of `<stdint.h>` and `<stddef.h>`. Names
+    /// that are not known are incomplete, so that they can still be pointed to.
+    fn named_type(&self, name: &str) -> CType {
+        if let Some(ty) = self.header.typedefs.get(name) {
+            return ty.clone();
+        }
+        match name {
+            "int8_t" | "uint8_t" => CType::Scalar(Scalar::Char),
+            "int16_t" | "uint16_t" | "char16_t" => CType::Scalar(Scalar::Short),
+            "int32_t" | "uint32_t" | "char32_t" => CType::Scalar(Scalar::Int),
+            "int64_t" | "uint64_t" => CType::Scalar(Scalar::LongLong),
+            "size_t" | "ssize_t" | "ptrdiff_t" | "intptr_t" | "uintptr_t" => CType::Pointer,
+            _ => CType::Incomplete(name.to_owned()),
+        }
+    }
+
+    /// Parses a struct or union specifier, registering its definition if it has one.
+    fn parse_record(&mut self) -> ParseResult<CType> {
+        let is_union = self.eat_ident("union");
+        if !is_union {
+            self.expect_ident("struct")?;
+        }
+        let keyword = if is_union { "union" } else { "struct" };
+        let tag = match self.peek() {
+            Some(Token::Ident(tag)) => {
+                let tag = format!("{} {}", keyword, tag);
+                self.pos += 1;
+                Some(tag)
+            }
+            _ => None,
+        };
+
+        if self.peek() != Some(&Token::Punct('{')) {
+            let tag = tag.ok_or_else(|| format!("line {}: expected a struct name or body", self.line()))?;
+            return Ok(match self.header.records.get(&tag) {
+                Some(record) => CType::Record(record.clone()),
+                None => CType::Incomplete(tag),
+            });
+        }
+
+        // As in C, the record is incomplete until its closing brace, so its own fields
+        // can only point to it.
+        let close = self.matching_brace(self.pos)?;
+        self.pos += 1;
+        let fields = self.parse_fields(close);
+        self.pos = close + 1;
+        let record = Rc::new(Record { is_union, fields });
+        if let Some(tag) = tag {
+            self.header.records.insert(tag, record.clone());
+        }
+        Ok(CType::Record(record))
+    }
+
+    /// Parses a declarator such as `*name`, `name[4][LEN]` or `(*name)(int)` applied to
+    /// `base`, returning the declared name and type.
+    fn parse_declarator(&mut self, base: &CType) -> ParseResult<(String, CType)> {
+        let mut ty = base.clone();
+        while self.eat_punct('*') {
+            ty = CType::Pointer;
+            while self.eat_ident("const") || self.eat_ident("volatile") || self.eat_ident("restrict") {}
+        }
+
+        // A function pointer, possibly in an array, as in `(*handlers[4])(int)`.
+        if self.eat_punct('(') {
+            self.expect_punct('*')?;
+            let name = self.expect_name()?;
+            let dims = self.parse_dims()?;
+            self.expect_punct(')')?;
+            self.expect_punct('(')?;
+            let mut depth = 1;
+            while depth > 0 {
+                match self.next() {
+                    Some(Token::Punct('(')) => depth += 1,
+                    Some(Token::Punct(')')) => depth -= 1,
+                    Some(_) => {}
+                    None => return Err("unexpected end of header".to_owned()),
+                }
+            }
+            return Ok((name, array_of(CType::Pointer, &dims)));
+        }
+
+        let name = self.expect_name()?;
+        let dims = self.parse_dims()?;
+        if self.peek() == Some(&Token::Punct(':')) {
+            return Err(format!("line {}: bit-field `{}` is not supported", self.line(), name));
+        }
+        Ok((name, array_of(ty, &dims)))
+    }
+
+    /// Parses the array lengths following a declarator name, as in `[4][LEN * 2]`.
+    fn parse_dims(&mut self) -> ParseResult<Vec<usize>> {
+        let mut dims = Vec::new();
+        while self.eat_punct('[') {
+            let (start, line) = (self.pos, self.line());
+            while !matches!(self.peek(), Some(Token::Punct(']')) | None) {
+                self.pos += 1;
+            }
+            let tokens: Vec<Token> = self.tokens[start..self.pos].iter().map(|(token, _)| token.clone()).collect();
+            self.expect_punct(']')?;
+            let tokens = expand(&tokens, &self.header.defines, 0).map_err(|err| format!("line {}: {}", line, err))?;
+            let len = Expression { tokens: &tokens, pos: 0 }
+                .evaluate()
+                .map_err(|err| format!("line {}: invalid array length: {}", line, err))?;
+            dims.push(len);
+        }
+        Ok(dims)
+    }
+
+    /// Skips a declaration that is not understood, up to its `;` or the end of a function
+    /// body.
+    fn skip_statement(&mut self) {
+        while let Some(token) = self.next() {
+            match token {
+                Token::Punct(';') => return,
+                Token::Punct('{') => {
+                    self.pos = self.matching_brace(self.pos - 1).map_or(self.tokens.len(), |close| close + 1);
+                    self.eat_punct(';');
+                    return;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Finds the `}` closing the `{` at `open`.
+    fn matching_brace(&self, open: usize) -> ParseResult<usize> {
+        let mut depth = 0;
+        for (index, (token, _)) in self.tokens.iter().enumerate().skip(open) {
+            match token {
+                Token::Punct('{') => depth += 1,
+                Token::Punct('}') => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(index);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Err(format!("line {}: unclosed `{{`", self.tokens[open].1))
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + offset).map(|(token, _)| token)
+    }
+
+    fn next(&mut self) -> Option<&Token> {
+        let token = self.tokens.get(self.pos).map(|(token, _)| token);
+        self.pos += 1;
+        token
+    }
+
+    /// The line of the current token, for error messages.
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .or(self.tokens.last())
+            .map_or(0, |(_, line)| *line)
+    }
+
+    fn eat_punct(&mut self, punct: char) -> bool {
+        let eaten = self.peek() == Some(&Token::Punct(punct));
+        self.pos += usize::from(eaten);
+        eaten
+    }
+
+    fn eat_ident(&mut self, ident: &str) -> bool {
+        let eaten = matches!(self.peek(), Some(Token::Ident(next)) if next == ident);
+        self.pos += usize::from(eaten);
+        eaten
+    }
+
+    fn expect_punct(&mut self, punct: char) -> ParseResult<()> {
+        if self.eat_punct(punct) {
+            Ok(())
+        } else {
+            Err(format!("line {}: expected `{}`", self.line(), punct))
+        }
+    }
+
+    fn expect_ident(&mut self, ident: &str) -> ParseResult<()> {
+        if self.eat_ident(ident) {
+            Ok(())
+        } else {
+            Err(format!("line {}: expected `{}`", self.line(), ident))
+        }
+    }
+
+    fn expect_name(&mut self) -> ParseResult<String> {
+        let line = self.line();
+        match self.next() {
+            Some(Token::Ident(name)) => Ok(name.clone()),
+            _ => Err(format!("line {}: expected a name", line)),
+        }
+    }
+}
+
+/// Wraps `ty` in arrays of the lengths `dims`, the first being the outermost.
+fn array_of(ty: CType, dims: &[usize]) -> CType {
+    dims.iter().rev().fold(ty, |ty, &len| CType::Array(Box::new(ty), len))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The size and field offsets of `name` in `text` on LP64.
+    fn lp64_layout(text: &str, name: &str) -> (usize, Vec<usize>) {
+        let header = Header::parse(text).unwrap();
+        let layout = ABIS[0].record_layout(header.record(name).unwrap()).unwrap();
+        (layout.size, layout.offsets)
+    }
+
+    #[test]
+    fn self_referential_struct() {
+        let text = "struct node { int v; struct node *next; };";
+        assert_eq!(lp64_layout(text, "struct node"), (16, vec![0, 8]));
+    }
+
+    #[test]
+    fn pointer_to_opaque_typedef() {
+        let text = "typedef struct opaque opaque_t;\nstruct h { opaque_t *p; char c; };";
+        assert_eq!(lp64_layout(text, "struct h"), (16, vec![0, 8]));
+    }
+}
@@ -0,0 +1,107 @@
+//! Code generation for the `discriminant` option and the `#[assert_discriminant(N)]`
+//! helper attribute, which check the discriminants of an enum.
+
+use proc_macro2::{Ident, Span, TokenStream as TokenStream2};
+use quote::{ToTokens, quote};
+use syn::{Data, DeriveInput, Error, Expr, Result, spanned::Spanned};
+
+use crate::args::Level;
+use crate::expand::{AssertedType, Diagnostic, failed_check, typed_equality_check};
+use crate::repr::primitive_repr;
+use crate::variants::take_variant_attributes;
+
+/// The name of the helper attribute that pins the discriminant of an enum variant.
+const ASSERT_DISCRIMINANT: &str = "assert_discriminant";
+
+/// An expected discriminant taken from an `#[assert_discriminant(N)]` helper attribute.
+pub(crate) struct PinnedDiscriminant {
+    /// The index of the variant in declaration order.
+    index: usize,
+    value: Expr,
+}
+
+/// Strips every `#[assert_discriminant(N)]` helper attribute from the variants of `input`
+/// and returns the discriminants they expect.
+pub(crate) fn take_discriminants(input: &mut DeriveInput) -> Result<Vec<PinnedDiscriminant>> {
+    Ok(take_variant_attributes(input, ASSERT_DISCRIMINANT)?
+        .into_iter()
+        .map(|(index, value)| PinnedDiscriminant { index, value })
+        .collect())
+}
+
+/// Checks the discriminant type given with `discriminant = u8` against the `#[repr]` of
+/// the annotated enum, and generates the const assertions for its pinned discriminants,
+/// for each of `types`.
+///
+/// Discriminants can only be read with `as` from enums without fields, so the value of
+/// each variant is computed the way the compiler assigns it instead: from the nearest
+/// explicit discriminant at or before it, counting up by one per variant.
+pub(crate) fn discriminant_assertions<V>(
+    input: &DeriveInput,
+    types: &[AssertedType<V>],
+    discriminant: Option<&Ident>,
+    pinned: &[PinnedDiscriminant],
+    level: Level,
+) -> Result<TokenStream2> {
+    let Data::Enum(data) = &input.data else {
+        let span = discriminant.map_or_else(Span::call_site, Ident::span);
+        return Err(Error::new(span, "`discriminant` is only supported on enums"));
+    };
+
+    let repr = primitive_repr(&input.attrs)?;
+    let mut checks = TokenStream2::new();
+    if let Some(expected) = discriminant {
+        let mismatch = match &repr {
+            Some(repr) if repr == expected => None,
+            Some(repr) => Some(format!(
+                "the discriminant of `{}` is `{}` from its `#[repr({})]`, but `{}` was expected",
+                input.ident, repr, repr, expected
+            )),
+            None => Some(format!("`discriminant = {}` requires `#[repr({})]` on `{}`", expected, expected, input.ident)),
+        };
+        if let Some(message) = mismatch {
+            checks.extend(failed_check(Error::new(expected.span(), message), level)?);
+        }
+    }
+
+    // Without a primitive representation, discriminants are `isize`.
+    let repr = repr.map_or_else(|| quote!(isize), |repr| repr.to_token_stream());
+    let mut values = Vec::new();
+    let mut explicit: Option<&Expr> = None;
+    let mut offset = 0usize;
+    for variant in &data.variants {
+        if let Some((_, value)) = &variant.discriminant {
+            explicit = Some(value);
+            offset = 0;
+        }
+        values.push(match explicit {
+            Some(value) => quote!({ let value: #repr = #value; value as i128 + #offset as i128 }),
+            None => quote!(#offset as i128),
+        });
+        offset += 1;
+    }
+
+    for asserted in types {
+        for PinnedDiscriminant { index, value } in pinned {
+            let variant = &data.variants[*index].ident;
+            let message = format!(
+                "discriminant of variant `{}` in `{{Self}}` is {{ACTUAL}}, but {{EXPECTED}} was expected",
+                variant
+            );
+            checks.extend(typed_equality_check(
+                &asserted.ty,
+                &quote!(i128),
+                quote!({ let value: #repr = #value; value as i128 }),
+                values[*index].clone(),
+                &Diagnostic {
+                    message: &message,
+                    label: "expected {EXPECTED}, found {ACTUAL}",
+                    warning: "DiscriminantOf",
+                },
+                asserted.span.unwrap_or_else(|| value.span()),
+                level,
+            ));
+        }
+    }
+    Ok(checks)
+}
@@ -0,0 +1,874 @@
+//! Code generation for the attribute macros.
+
+use proc_macro2::{Delimiter, Group, Span, TokenStream as TokenStream2, TokenTree};
+use quote::{ToTokens, quote, quote_spanned};
+use syn::{
+    Data, DeriveInput, Error, Field, GenericParam, Ident, Lifetime, Member, Result, Type, parse_quote,
+    spanned::Spanned, visit_mut::VisitMut
+};
+
+use crate::args::{
+    AssertAlignAttributeArgs, AssertSizeAttributeArgs, AssertSizesArgs, Bound, BoundOp, DesiredSize, Expectation,
+    Expected, Instantiations, Level, TargetArm
+};
+use crate::item::AnnotatedItem;
+use crate::{c_export, c_header, discriminant, padding, repr, report, snapshot, variants};
+
+/// The name of the helper attribute that pins a field's offset.
+const ASSERT_OFFSET: &str = "assert_offset";
+
+/// The name of the helper attribute holding the arguments of `#[derive(AssertSize)]`.
+const SIZE: &str = "size";
+
+/// An expected field offset taken from an `#[assert_offset(N)]` helper attribute.
+struct FieldOffset {
+    member: Member,
+    desired_offset_in_bytes: Expected,
+}
+
+/// A type to assert on, either the annotated type itself or one of the instantiations
+/// listed in `for = [...]`.
+pub(crate) struct AssertedType<V> {
+    pub(crate) ty: Type,
+    /// The instantiation's own expected value, if it gave one with `=> N`.
+    pub(crate) desired_value: Option<V>,
+    /// Where mismatches are reported for an instantiation. Mismatches for the annotated
+    /// type itself, and of an instantiation's own expected size, are reported on the
+    /// expected value instead.
+    pub(crate) span: Option<Span>,
+}
+
+impl<V: Clone> AssertedType<V> {
+    /// Resolves the expected value for this type, falling back to `default`.
+    fn desired_value(&self, default: &V) -> V {
+        self.desired_value.clone().unwrap_or_else(|| default.clone())
+    }
+
+    /// The span a mismatch against `expected` is reported at.
+    fn span_for(&self, expected: &Expected) -> Span {
+        self.span.unwrap_or(expected.span)
+    }
+}
+
+/// Generates the assertions for `#[assert_size]`. Any `#[assert_offset]`,
+/// `#[variant_size]` and `#[assert_discriminant]` helper attributes are removed from
+/// `input`, even on error, so the item can be emitted as is.
+pub(crate) fn expand_assert_size(args: &AssertSizeAttributeArgs, input: &mut DeriveInput) -> Result<TokenStream2> {
+    let offsets = take_field_offsets(input);
+    let variant_sizes = variants::take_variant_sizes(input);
+    let discriminants = discriminant::take_discriminants(input);
+    let (offsets, variant_sizes, discriminants) = (offsets?, variant_sizes?, discriminants?);
+    let level = args.level();
+    let repr = repr::check_repr(input, args.repr.as_ref(), args.forbid_repr.as_ref(), level)?;
+    let types = asserted_types(args.instantiations.as_ref(), input, "assert_size")?;
+    let unmatched_target = match &args.desired_size_in_bytes {
+        Some(DesiredSize::PerTarget(arms)) => unmatched_target_error(arms, &input.ident.to_string()),
+        _ => TokenStream2::new(),
+    };
+
+    let assertions: TokenStream2 = types
+        .iter()
+        .map(|asserted| {
+            let offsets = offsets.iter().map(|offset| offset_assertion(&asserted.ty, offset, level));
+            let type_assertions = type_assertions(args, asserted);
+            quote!(#type_assertions #(#offsets)*)
+        })
+        .collect();
+    let no_padding = args
+        .no_padding
+        .map(|span| padding::no_padding_assertions(input, &types, span, level))
+        .transpose()?;
+    let variants = if variant_sizes.is_empty() && args.max_variant_ratio.is_none() {
+        None
+    } else {
+        Some(variants::variant_assertions(input, &types, &variant_sizes, args.max_variant_ratio.as_ref(), level)?)
+    };
+    let discriminants = if discriminants.is_empty() && args.discriminant.is_none() {
+        None
+    } else {
+        Some(discriminant::discriminant_assertions(input, &types, args.discriminant.as_ref(), &discriminants, level)?)
+    };
+    let report = args
+        .report
+        .map(|span| report::layout_report(input, span))
+        .transpose()?;
+    let test = args.test.as_ref().map(|test| report::layout_test(input, &types, test));
+    let snapshot = args
+        .snapshot
+        .as_ref()
+        .map(|snapshot| snapshot::snapshot_assertions(input, &types, snapshot, level))
+        .transpose()?;
+    let c_struct = args
+        .c_struct
+        .as_ref()
+        .map(|c_struct| c_header::c_header_assertions(input, &types, c_struct, level))
+        .transpose()?;
+    if let Some(export) = &args.c_export {
+        c_export::export_c_declaration(input, args, export)?;
+    }
+
+    Ok(quote!(#unmatched_target #repr #assertions #no_padding #variants #discriminants #report #test #snapshot #c_struct))
+}
+
+/// Generates the assertions for `#[derive(AssertSize)]` from its `#[size(...)]` helper
+/// attribute.
+pub(crate) fn expand_derive_assert_size(input: &DeriveInput) -> Result<TokenStream2> {
+    let mut helpers = input.attrs.iter().filter(|attr| attr.path().is_ident(SIZE));
+    let Some(helper) = helpers.next() else {
+        return Err(Error::new(
+            Span::call_site(),
+            "`#[derive(AssertSize)]` requires a `#[size(...)]` attribute with the expected size",
+        ));
+    };
+    if let Some(duplicate) = helpers.next() {
+        return Err(Error::new_spanned(duplicate, "duplicate `size` attribute"));
+    }
+
+    // A derive cannot change the item, so the `#[assert_offset]` helper attributes are
+    // left in place and only taken from a copy.
+    expand_assert_size(&helper.parse_args()?, &mut input.clone())
+}
+
+pub(crate) fn expand_assert_align(args: &AssertAlignAttributeArgs, input: &DeriveInput) -> Result<TokenStream2> {
+    let types = asserted_types(args.instantiations.as_ref(), input, "assert_align")?;
+    let level = args.level();
+
+    Ok(types
+        .iter()
+        .map(|asserted| {
+            let desired_align_in_bytes = asserted.desired_value(&args.desired_align_in_bytes);
+            align_assertion(&asserted.ty, &desired_align_in_bytes, asserted.span_for(&desired_align_in_bytes), level)
+        })
+        .collect())
+}
+
+/// Generates the assertions for `#[assert_size]` on any of the items it can be placed on.
+pub(crate) fn expand_assert_size_item(args: &AssertSizeAttributeArgs, item: &mut AnnotatedItem) -> Result<TokenStream2> {
+    let (ty, generics) = match item {
+        AnnotatedItem::Definition(input) => return expand_assert_size(args, input),
+        AnnotatedItem::Alias(alias) => (&*alias.ty, &alias.generics),
+        AnnotatedItem::Impl(block) => (&*block.self_ty, &block.generics),
+    };
+    // Unlike a type definition, the type and const parameters of an alias or impl block
+    // can appear anywhere in the type, so there is no instantiation to substitute them
+    // with. Lifetimes do not affect the size of a type, so they are filled in with
+    // `'static` as for type definitions.
+    if let Some(param) = generics.params.iter().find(|param| !matches!(param, GenericParam::Lifetime(_))) {
+        return Err(Error::new_spanned(
+            param,
+            "`assert_size` is not supported on generic type aliases and impl blocks; check their instantiations with `assert_sizes!`",
+        ));
+    }
+    let mut ty = ty.clone();
+    StaticLifetimes(generics.lifetimes().map(|param| param.lifetime.ident.clone()).collect()).visit_type_mut(&mut ty);
+    expand_type(&ty, args)
+}
+
+/// Replaces the lifetimes with the given names by `'static`.
+struct StaticLifetimes(Vec<Ident>);
+
+impl VisitMut for StaticLifetimes {
+    fn visit_lifetime_mut(&mut self, lifetime: &mut Lifetime) {
+        if self.0.contains(&lifetime.ident) {
+            lifetime.ident = Ident::new("static", lifetime.ident.span());
+        }
+    }
+}
+
+/// Generates the assertions for `assert_sizes!`, one group per listed type.
+pub(crate) fn expand_assert_sizes(args: &AssertSizesArgs) -> Result<TokenStream2> {
+    args.entries.iter().map(|entry| expand_type(&entry.ty, &entry.args)).collect()
+}
+
+/// Generates the assertions for a type given by name rather than by its definition.
+fn expand_type(ty: &Type, args: &AssertSizeAttributeArgs) -> Result<TokenStream2> {
+    let unsupported = [
+        ("no_padding", args.no_padding),
+        ("max_variant_ratio", args.max_variant_ratio.as_ref().map(|ratio| ratio.span)),
+        ("discriminant", args.discriminant.as_ref().map(|discriminant| discriminant.span())),
+        ("repr", args.repr.as_ref().map(|repr| repr.span)),
+        ("forbid_repr", args.forbid_repr.as_ref().map(|repr| repr.span)),
+        ("report", args.report),
+        ("test", args.test.as_ref().map(|test| test.span)),
+        ("snapshot", args.snapshot.as_ref().map(|snapshot| snapshot.span)),
+        ("c_header", args.c_struct.as_ref().map(|c_struct| c_struct.header.span())),
+        ("c_export", args.c_export.as_ref().map(|export| export.span)),
+    ]
+    .into_iter()
+    .chain(args.instantiations.iter().map(|_| ("for", Some(ty.span()))));
+    for (option, span) in unsupported {
+        if let Some(span) = span {
+            return Err(Error::new(
+                span,
+                format!("`{}` needs the type definition and is only supported on structs, enums and unions", option),
+            ));
+        }
+    }
+
+    let unmatched_target = match &args.desired_size_in_bytes {
+        Some(DesiredSize::PerTarget(arms)) => unmatched_target_error(arms, &type_to_string(ty)),
+        _ => TokenStream2::new(),
+    };
+    let asserted = AssertedType { ty: ty.clone(), desired_value: None, span: None };
+    let type_assertions = type_assertions(args, &asserted);
+    Ok(quote!(#unmatched_target #type_assertions))
+}
+
+/// Generates the assertions that `args` make about a single type, other than those that
+/// need the type definition.
+fn type_assertions(args: &AssertSizeAttributeArgs, asserted: &AssertedType<Expectation>) -> TokenStream2 {
+    let level = args.level();
+    let size = match (&asserted.desired_value, &args.desired_size_in_bytes) {
+        (Some(expectation), _) | (None, Some(DesiredSize::Uniform(expectation))) => {
+            size_assertion(asserted, expectation, level)
+        }
+        (None, Some(DesiredSize::PerTarget(arms))) => {
+            per_target_assertions(arms, |expectation| size_assertion(asserted, expectation, level))
+        }
+        (None, None) => TokenStream2::new(),
+    };
+    let align = args.desired_align_in_bytes.as_ref().map(|desired_align_in_bytes| {
+        align_assertion(&asserted.ty, desired_align_in_bytes, asserted.span_for(desired_align_in_bytes), level)
+    });
+    let same_size = args
+        .same_size_as
+        .iter()
+        .chain(&args.same_layout_as)
+        .map(|other| size_assertion(asserted, &Expectation::Exact(size_of(other)), level));
+    let same_align = args.same_layout_as.as_ref().map(|other| {
+        let expected = align_of(other);
+        align_assertion(&asserted.ty, &expected, asserted.span_for(&expected), level)
+    });
+    let option_same_size = args
+        .option_same_size
+        .map(|span| option_same_size_assertion(&asserted.ty, asserted.span.unwrap_or(span), level));
+    quote!(#size #align #(#same_size)* #same_align #option_same_size)
+}
+
+/// Generates the const assertions for the size of a single type, one per bound when the
+/// size is bounded rather than exact.
+fn size_assertion(asserted: &AssertedType<Expectation>, desired_size_in_bytes: &Expectation, level: Level) -> TokenStream2 {
+    let ty = &asserted.ty;
+    expectation_check(
+        ty,
+        desired_size_in_bytes,
+        quote!(::core::mem::size_of::<#ty>()),
+        "size of `{Self}`",
+        "SizeOf",
+        // An instantiation's own size is written right after it, and is what must change.
+        |expected| if asserted.desired_value.is_some() { expected.span } else { asserted.span_for(expected) },
+        level,
+    )
+}
+
+/// Generates the const checks that `actual` meets `expectation` for `ty`, one per bound
+/// when it is bounded rather than exact, each reported at the span `span_for` gives its
+/// expected value. `subject` and `warning` are as for [`mismatch_check`].
+pub(crate) fn expectation_check(
+    ty: &Type,
+    expectation: &Expectation,
+    actual: TokenStream2,
+    subject: &str,
+    warning: &str,
+    span_for: impl Fn(&Expected) -> Span,
+    level: Level,
+) -> TokenStream2 {
+    match expectation {
+        Expectation::Exact(expected) => mismatch_check(ty, expected, actual, subject, warning, span_for(expected), level),
+        Expectation::Bounded(bounds) => bounds
+            .iter()
+            .map(|bound| bound_check(ty, bound, &actual, subject, warning, span_for(&bound.value), level))
+            .collect(),
+    }
+}
+
+/// The size of another type as an expected value, for `same_as` and `same_layout_as`.
+fn size_of(other: &Type) -> Expected {
+    Expected {
+        expr: parse_quote!(::core::mem::size_of::<#other>()),
+        literal: None,
+        span: other.span(),
+    }
+}
+
+/// The alignment of another type as an expected value, for `same_layout_as`.
+fn align_of(other: &Type) -> Expected {
+    Expected {
+        expr: parse_quote!(::core::mem::align_of::<#other>()),
+        literal: None,
+        span: other.span(),
+    }
+}
+
+/// Gates the assertions generated for each target arm behind the arm's `cfg` predicate,
+/// such that only the first arm matching the target is checked.
+fn per_target_assertions(arms: &[TargetArm], mut assertions: impl FnMut(&Expectation) -> TokenStream2) -> TokenStream2 {
+    let mut previous = Vec::new();
+    arms.iter()
+        .map(|arm| {
+            let inner = assertions(&arm.desired_size_in_bytes);
+            let predicate = match &arm.predicate {
+                Some(predicate) => quote!(all(#predicate, not(any(#(#previous),*)))),
+                None => quote!(not(any(#(#previous),*))),
+            };
+            previous.extend(arm.predicate.clone());
+            quote! {
+                #[cfg(#predicate)]
+                const _: () = {
+                    #inner
+                };
+            }
+        })
+        .collect()
+}
+
+/// Generates a compile error for targets that none of the arms match, unless there is a
+/// `_` fallback arm.
+fn unmatched_target_error(arms: &[TargetArm], type_name: &str) -> TokenStream2 {
+    if arms.iter().any(|arm| arm.predicate.is_none()) {
+        return TokenStream2::new();
+    }
+
+    let predicates = arms.iter().filter_map(|arm| arm.predicate.as_ref());
+    let message = format!(
+        "no `assert_size` arm for `{}` matches the current target; add a `_ => N` arm to cover other targets",
+        type_name
+    );
+    quote_spanned! {arms[0].span=>
+        #[cfg(not(any(#(#predicates),*)))]
+        ::core::compile_error!(#message);
+    }
+}
+
+/// Generates the const assertion for the alignment of a single type.
+fn align_assertion(ty: &Type, desired_align_in_bytes: &Expected, span: Span, level: Level) -> TokenStream2 {
+    mismatch_check(
+        ty,
+        desired_align_in_bytes,
+        quote!(::core::mem::align_of::<#ty>()),
+        "alignment of `{Self}`",
+        "AlignOf",
+        span,
+        level,
+    )
+}
+
+/// Generates the const assertion that `Option<ty>` is no larger than `ty`, i.e. that `ty`
+/// has a niche in which `None` can be stored.
+fn option_same_size_assertion(ty: &Type, span: Span, level: Level) -> TokenStream2 {
+    equality_check(
+        ty,
+        quote!(::core::mem::size_of::<#ty>()),
+        quote!(::core::mem::size_of::<::core::option::Option<#ty>>()),
+        &Diagnostic {
+            message: "`Option<{Self}>` is {ACTUAL} bytes, but `{Self}` is {EXPECTED} bytes: the niche that stores `None` was lost",
+            label: "expected `Option<{Self}>` to be {EXPECTED} bytes, found {ACTUAL} bytes",
+            warning: "SizeOfOption",
+        },
+        span,
+        level,
+    )
+}
+
+/// Generates the const assertion for the offset of a single field of `ty`, reported on
+/// the offset given in its `#[assert_offset(N)]` attribute.
+fn offset_assertion(ty: &Type, offset: &FieldOffset, level: Level) -> TokenStream2 {
+    let FieldOffset { member, desired_offset_in_bytes } = offset;
+    let subject = format!("offset of field `{}` in `{{Self}}`", member.to_token_stream());
+    mismatch_check(
+        ty,
+        desired_offset_in_bytes,
+        quote!(::core::mem::offset_of!(#ty, #member)),
+        &subject,
+        "OffsetOf",
+        desired_offset_in_bytes.span,
+        level,
+    )
+}
+
+/// Generates a const check that `actual` evaluates to `expected` for `ty`, where
+/// `subject` describes what `actual` measures and may refer to the type as `{Self}`, and
+/// `warning` names it in warnings as described in [`Diagnostic`].
+fn mismatch_check(
+    ty: &Type,
+    expected: &Expected,
+    actual: TokenStream2,
+    subject: &str,
+    warning: &str,
+    span: Span,
+    level: Level,
+) -> TokenStream2 {
+    let message = format!(
+        "{} is {{ACTUAL}} bytes, but {} were expected",
+        subject,
+        describe(expected, "EXPECTED")
+    );
+    let expected = &expected.expr;
+    equality_check(
+        ty,
+        quote!(#expected),
+        actual,
+        &Diagnostic { message: &message, label: "expected {EXPECTED} bytes, found {ACTUAL} bytes", warning },
+        span,
+        level,
+    )
+}
+
+/// How a failed check is reported.
+pub(crate) struct Diagnostic<'a> {
+    /// The `on_unimplemented` message of the error, which may refer to the expected and
+    /// actual values as `{EXPECTED}` and `{ACTUAL}` and to the type as `{Self}`.
+    pub(crate) message: &'a str,
+    /// The `on_unimplemented` label of the error, with the same placeholders.
+    pub(crate) label: &'a str,
+    /// The name of the type a warning shows instead, such as `SizeOf`, which is printed
+    /// as in `SizeOf<Header, Expected<8>, Found<16>>`.
+    pub(crate) warning: &'a str,
+}
+
+/// Generates a const check that `actual` evaluates to `expected` for `ty`.
+///
+/// A plain `assert!` only reports that constant evaluation failed. Instead, both values
+/// are passed as const generics to a function bounded by a trait that is only
+/// implemented when they are equal, so a mismatch becomes an unsatisfied trait bound
+/// whose `on_unimplemented` message and label can print both numbers as `{EXPECTED}` and
+/// `{ACTUAL}`, and the type as `{Self}`. The type argument is respanned to `span`, which
+/// is where rustc reports the unsatisfied bound.
+///
+/// At [`Level::Warn`], the check is made by [`warning_check`] instead.
+pub(crate) fn equality_check(
+    ty: &Type,
+    expected: TokenStream2,
+    actual: TokenStream2,
+    diagnostic: &Diagnostic,
+    span: Span,
+    level: Level,
+) -> TokenStream2 {
+    typed_equality_check(ty, &quote!(usize), expected, actual, diagnostic, span, level)
+}
+
+/// Generates a const check like [`equality_check`], for values of `value_type` rather
+/// than `usize`, which must be an integer type usable as a const generic.
+pub(crate) fn typed_equality_check(
+    ty: &Type,
+    value_type: &TokenStream2,
+    expected: TokenStream2,
+    actual: TokenStream2,
+    diagnostic: &Diagnostic,
+    span: Span,
+    level: Level,
+) -> TokenStream2 {
+    if level == Level::Warn {
+        return warning_check(ty, diagnostic.warning, value_type, None, &expected, &actual, span);
+    }
+
+    let Diagnostic { message, label, .. } = diagnostic;
+    let checked_ty = respan(ty.to_token_stream(), span);
+    quote_spanned! {span=>
+        const _: () = {
+            #[diagnostic::on_unimplemented(message = #message, label = #label)]
+            trait Matches<const EXPECTED: #value_type, const ACTUAL: #value_type> {}
+            impl<T: ?Sized, const N: #value_type> Matches<N, N> for T {}
+
+            const fn check<T, const EXPECTED: #value_type, const ACTUAL: #value_type>()
+            where
+                T: ?Sized + Matches<EXPECTED, ACTUAL>,
+            {
+            }
+
+            check::<#checked_ty, { #expected }, { #actual }>();
+        };
+    }
+}
+
+/// Generates a const check that `actual` satisfies `bound` for `ty`, reported the same
+/// way as [`mismatch_check`] but with the violated bound in the message.
+fn bound_check(
+    ty: &Type,
+    bound: &Bound,
+    actual: &TokenStream2,
+    subject: &str,
+    warning: &str,
+    span: Span,
+    level: Level,
+) -> TokenStream2 {
+    let Bound { op, value } = bound;
+    let message = format!(
+        "{} is {{ACTUAL}} bytes, but it must be {} {}",
+        subject,
+        op.as_str(),
+        describe(value, "BOUND")
+    );
+    let label = format!("expected {} {{BOUND}} bytes, found {{ACTUAL}} bytes", op.as_str());
+    let value = &value.expr;
+    comparison_check(
+        ty,
+        *op,
+        &quote!(#value),
+        actual,
+        &Diagnostic { message: &message, label: &label, warning },
+        span,
+        level,
+    )
+}
+
+/// Generates a const check that `actual` compares to `bound` with `op` for `ty`, reported
+/// the same way as [`equality_check`] but with the value of `bound` available as
+/// `{BOUND}` rather than `{EXPECTED}`.
+pub(crate) fn comparison_check(
+    ty: &Type,
+    op: BoundOp,
+    bound: &TokenStream2,
+    actual: &TokenStream2,
+    diagnostic: &Diagnostic,
+    span: Span,
+    level: Level,
+) -> TokenStream2 {
+    if level == Level::Warn {
+        return warning_check(ty, diagnostic.warning, &quote!(usize), Some(op), bound, actual, span);
+    }
+
+    let Diagnostic { message, label, .. } = diagnostic;
+    let checked_ty = respan(ty.to_token_stream(), span);
+    quote_spanned! {span=>
+        const _: () = {
+            #[diagnostic::on_unimplemented(message = #message, label = #label)]
+            trait WithinBound<const ACTUAL: usize, const BOUND: usize, const WITHIN: bool> {}
+            impl<T: ?Sized, const ACTUAL: usize, const BOUND: usize> WithinBound<ACTUAL, BOUND, true> for T {}
+
+            const fn check<T, const ACTUAL: usize, const BOUND: usize, const WITHIN: bool>()
+            where
+                T: ?Sized + WithinBound<ACTUAL, BOUND, WITHIN>,
+            {
+            }
+
+            check::<#checked_ty, { #actual }, { #bound }, { (#actual) #op (#bound) }>();
+        };
+    }
+}
+
+/// Generates a check that reports a warning rather than an error for `level = "warn"`,
+/// when `actual` is not equal to `expected`, or does not compare to it with `op`.
+///
+/// Only the `on_unimplemented` message of an error can be customized, but a warning can
+/// still show both values through the type it prints: a call returns a value of the local
+/// type named `warning`, as in `SizeOf<Header, Expected<8>, Found<16>>`, when the check
+/// fails and `()` otherwise, and `unused_results` reports the unused value with its type.
+/// A bound is shown as `AtMost<64>` and the like instead of `Expected<64>`.
+fn warning_check(
+    ty: &Type,
+    warning: &str,
+    value_type: &TokenStream2,
+    op: Option<BoundOp>,
+    expected: &TokenStream2,
+    actual: &TokenStream2,
+    span: Span,
+) -> TokenStream2 {
+    let (wrapper, holds) = match op {
+        None => ("Expected", quote!((#expected) == (#actual))),
+        Some(op) => {
+            let wrapper = match op {
+                BoundOp::Less => "LessThan",
+                BoundOp::LessOrEqual => "AtMost",
+                BoundOp::Greater => "MoreThan",
+                BoundOp::GreaterOrEqual => "AtLeast",
+            };
+            (wrapper, quote!((#actual) #op (#expected)))
+        }
+    };
+    let warning = Ident::new(warning, span);
+    let wrapper = Ident::new(wrapper, span);
+    let checked_ty = respan(ty.to_token_stream(), span);
+    quote_spanned! {span=>
+        #[allow(dead_code)]
+        const _: () = {
+            struct #wrapper<const N: #value_type>;
+            struct Found<const N: #value_type>;
+            struct #warning<T: ?Sized, E, F>(::core::marker::PhantomData<(*const T, E, F)>);
+
+            struct Check<const EXPECTED: #value_type, const ACTUAL: #value_type, const HOLDS: bool>;
+            impl<const EXPECTED: #value_type, const ACTUAL: #value_type> Check<EXPECTED, ACTUAL, true> {
+                fn check<T: ?Sized>() {}
+            }
+            impl<const EXPECTED: #value_type, const ACTUAL: #value_type> Check<EXPECTED, ACTUAL, false> {
+                fn check<T: ?Sized>() -> #warning<T, #wrapper<EXPECTED>, Found<ACTUAL>> {
+                    #warning(::core::marker::PhantomData)
+                }
+            }
+
+            #[warn(unused_results)]
+            fn check() {
+                Check::<{ #expected }, { #actual }, { #holds }>::check::<#checked_ty>();
+            }
+        };
+    }
+}
+
+/// Reports `error`, a check that already failed while the macro expands, such as a missing
+/// `#[repr]` hint. At [`Level::Warn`], each of its messages becomes the note of a warning
+/// about a deprecated constant, as stable Rust has no other way for a macro to warn.
+pub(crate) fn failed_check(error: Error, level: Level) -> Result<TokenStream2> {
+    if level == Level::Error {
+        return Err(error);
+    }
+    Ok(error
+        .into_iter()
+        .map(|error| {
+            let message = error.to_string();
+            quote_spanned! {error.span()=>
+                const _: () = {
+                    #[deprecated(note = #message)]
+                    const FAILED: () = ();
+                    FAILED
+                };
+            }
+        })
+        .collect())
+}
+
+/// Describes an expected number of bytes in an `on_unimplemented` message, where its
+/// value is available as the const parameter `param`. Expressions other than literals are
+/// quoted too, so the message shows where the number came from.
+fn describe(expected: &Expected, param: &str) -> String {
+    match expected.literal {
+        Some(_) => format!("{{{}}} bytes", param),
+        None => {
+            let expr = expr_to_string(expected.expr.to_token_stream())
+                .replace('{', "{{")
+                .replace('}', "}}");
+            format!("{{{}}} bytes (`{}`)", param, expr)
+        }
+    }
+}
+
+/// Prints an expression roughly the way it would be written by hand. The `Display` output
+/// of a token stream puts spaces between all tokens, as in `size_of :: < u64 > ()`.
+fn expr_to_string(tokens: TokenStream2) -> String {
+    print_tokens(tokens, false)
+}
+
+/// Prints a type the way [`expr_to_string`] prints an expression.
+pub(crate) fn type_to_string(ty: &Type) -> String {
+    print_tokens(ty.to_token_stream(), true)
+}
+
+/// Prints `tokens` as an expression, or as a type if `in_type` is set, in which case every
+/// `<` opens generic arguments rather than only those of a turbofish.
+fn print_tokens(tokens: TokenStream2, in_type: bool) -> String {
+    let mut printed = String::new();
+    // Nesting depth of generic arguments, whose angle brackets are printed without
+    // surrounding spaces.
+    let mut generics_depth = usize::from(in_type);
+    let mut previous: Option<TokenTree> = None;
+    // Whether `previous` takes a reference rather than being a bitwise and, as in `&'a T`.
+    let mut unary_ampersand = false;
+
+    for token in tokens {
+        let space = match (&previous, &token) {
+            (None, _) => false,
+            (Some(TokenTree::Punct(prev)), _) if matches!(prev.as_char(), ':' | '.' | '\'') => false,
+            (Some(TokenTree::Punct(prev)), _) if prev.as_char() == '&' && unary_ampersand => false,
+            (Some(TokenTree::Punct(prev)), _) if prev.as_char() == '<' && generics_depth > 0 => false,
+            (_, TokenTree::Punct(punct)) if matches!(punct.as_char(), ':' | '.' | ',' | ';') => false,
+            (_, TokenTree::Punct(punct)) if matches!(punct.as_char(), '<' | '>') && generics_depth > 0 => false,
+            (Some(TokenTree::Ident(_)), TokenTree::Group(group)) => group.delimiter() == Delimiter::Brace,
+            (Some(TokenTree::Punct(prev)), TokenTree::Group(_)) => prev.as_char() != '>' && prev.as_char() != '&',
+            _ => true,
+        };
+        if space {
+            printed.push(' ');
+        }
+
+        match &token {
+            TokenTree::Punct(punct) if punct.as_char() == '<' => {
+                let turbofish = matches!(&previous, Some(TokenTree::Punct(prev)) if prev.as_char() == ':');
+                if turbofish || generics_depth > 0 {
+                    generics_depth += 1;
+                }
+                printed.push('<');
+            }
+            TokenTree::Punct(punct) if punct.as_char() == '>' && generics_depth > 0 => {
+                generics_depth -= 1;
+                printed.push('>');
+            }
+            TokenTree::Group(group) => {
+                let inner = print_tokens(group.stream(), in_type);
+                match group.delimiter() {
+                    Delimiter::Parenthesis => printed.push_str(&format!("({})", inner)),
+                    Delimiter::Bracket => printed.push_str(&format!("[{}]", inner)),
+                    Delimiter::Brace => printed.push_str(&format!("{{ {} }}", inner)),
+                    Delimiter::None => printed.push_str(&inner),
+                }
+            }
+            token => printed.push_str(&token.to_string()),
+        }
+        unary_ampersand = matches!(&token, TokenTree::Punct(punct) if punct.as_char() == '&')
+            && matches!(&previous, None | Some(TokenTree::Punct(_)));
+        previous = Some(token);
+    }
+
+    printed
+}
+
+/// Sets the span of every token in `tokens` to `span`.
+fn respan(tokens: TokenStream2, span: Span) -> TokenStream2 {
+    tokens
+        .into_iter()
+        .map(|token| match token {
+            TokenTree::Group(group) => {
+                let mut respanned = Group::new(group.delimiter(), respan(group.stream(), span));
+                respanned.set_span(span);
+                TokenTree::Group(respanned)
+            }
+            mut token => {
+                token.set_span(span);
+                token
+            }
+        })
+        .collect()
+}
+
+/// Strips every `#[assert_offset(N)]` helper attribute from the fields of `input` and
+/// returns the offsets they expect. Only struct fields can carry the attribute.
+fn take_field_offsets(input: &mut DeriveInput) -> Result<Vec<FieldOffset>> {
+    let (fields, is_struct): (Vec<&mut Field>, bool) = match &mut input.data {
+        Data::Struct(data) => (data.fields.iter_mut().collect(), true),
+        Data::Enum(data) => (
+            data.variants
+                .iter_mut()
+                .flat_map(|variant| variant.fields.iter_mut())
+                .collect(),
+            false,
+        ),
+        Data::Union(data) => (data.fields.named.iter_mut().collect(), false),
+    };
+
+    let mut offsets = Vec::new();
+    let mut errors = Vec::new();
+    for (index, field) in fields.into_iter().enumerate() {
+        let (helpers, attrs) = field
+            .attrs
+            .drain(..)
+            .partition(|attr| attr.path().is_ident(ASSERT_OFFSET));
+        field.attrs = attrs;
+
+        let mut helpers = helpers.into_iter();
+        let Some(helper) = helpers.next() else {
+            continue;
+        };
+
+        if !is_struct {
+            errors.push(Error::new_spanned(
+                &helper,
+                "`assert_offset` is only supported on struct fields",
+            ));
+            continue;
+        }
+        if let Some(duplicate) = helpers.next() {
+            errors.push(Error::new_spanned(duplicate, "duplicate `assert_offset` attribute"));
+            continue;
+        }
+
+        match helper.parse_args::<Expected>() {
+            Ok(desired_offset_in_bytes) => offsets.push(FieldOffset {
+                member: match &field.ident {
+                    Some(ident) => Member::Named(ident.clone()),
+                    None => Member::Unnamed(index.into()),
+                },
+                desired_offset_in_bytes,
+            }),
+            Err(err) => errors.push(err),
+        }
+    }
+
+    combine_errors(errors)?;
+    Ok(offsets)
+}
+
+/// Reports all of `errors` at once, or succeeds if there are none.
+pub(crate) fn combine_errors(errors: Vec<Error>) -> Result<()> {
+    match errors.into_iter().reduce(|mut errors, err| {
+        errors.combine(err);
+        errors
+    }) {
+        Some(errors) => Err(errors),
+        None => Ok(()),
+    }
+}
+
+/// Checks that an instantiation in `for = [...]` names the annotated type.
+fn check_instantiation(input: &DeriveInput, ty: &Type) -> Result<()> {
+    let names_input = match ty {
+        Type::Path(path) if path.qself.is_none() => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == input.ident),
+        _ => false,
+    };
+
+    if names_input {
+        Ok(())
+    } else {
+        Err(Error::new_spanned(
+            ty,
+            format!("expected an instantiation of `{}`", input.ident),
+        ))
+    }
+}
+
+/// Collects the types to assert on: the annotated type itself, or each instantiation
+/// listed in `for = [...]` when the type is generic.
+fn asserted_types<V: Clone>(
+    instantiations: Option<&Instantiations<V>>,
+    input: &DeriveInput,
+    macro_name: &str,
+) -> Result<Vec<AssertedType<V>>> {
+    let type_name = &input.ident;
+    let needs_instantiation = input
+        .generics
+        .params
+        .iter()
+        .any(|param| !matches!(param, GenericParam::Lifetime(_)));
+
+    match instantiations {
+        Some(instantiations) => {
+            if !needs_instantiation {
+                return Err(Error::new_spanned(
+                    type_name,
+                    "`for = [...]` is only needed on types with type or const parameters",
+                ));
+            }
+
+            instantiations
+                .iter()
+                .map(|instantiation| {
+                    check_instantiation(input, &instantiation.ty)?;
+                    Ok(AssertedType {
+                        ty: instantiation.ty.clone(),
+                        desired_value: instantiation.desired_value.clone(),
+                        span: Some(instantiation.ty.span()),
+                    })
+                })
+                .collect()
+        }
+        None if needs_instantiation => Err(Error::new_spanned(
+            &input.generics,
+            format!(
+                "`{}` on a generic type needs concrete instantiations, e.g. `for = [{}<...>]`",
+                macro_name, type_name
+            ),
+        )),
+        None => {
+            // Lifetime-only generics are filled in with `'static` since lifetimes do not
+            // affect the size of a type.
+            let lifetimes = input.generics.lifetimes().map(|_| quote!('static));
+            let ty = if input.generics.params.is_empty() {
+                syn::parse_quote!(#type_name)
+            } else {
+                syn::parse_quote!(#type_name<#(#lifetimes),*>)
+            };
+            Ok(vec![AssertedType { ty, desired_value: None, span: None }])
+        }
+    }
+}
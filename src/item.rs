@@ -0,0 +1,40 @@
+//! The items that `#[assert_size]` can be placed on.
+
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::ToTokens;
+use syn::{DeriveInput, Error, Item, ItemImpl, ItemType, Result, parse::{Parse, ParseStream}};
+
+pub(crate) enum AnnotatedItem {
+    /// A struct, enum or union definition.
+    Definition(DeriveInput),
+    /// A type alias such as `type Packet = Header<u16>;`, whose aliased type is checked.
+    Alias(ItemType),
+    /// An impl block, whose self type is checked.
+    Impl(ItemImpl),
+}
+
+impl Parse for AnnotatedItem {
+    fn parse(input: ParseStream) -> Result<Self> {
+        match input.parse()? {
+            Item::Struct(item) => Ok(AnnotatedItem::Definition(item.into())),
+            Item::Enum(item) => Ok(AnnotatedItem::Definition(item.into())),
+            Item::Union(item) => Ok(AnnotatedItem::Definition(item.into())),
+            Item::Type(item) => Ok(AnnotatedItem::Alias(item)),
+            Item::Impl(item) => Ok(AnnotatedItem::Impl(item)),
+            _ => Err(Error::new(
+                Span::call_site(),
+                "`assert_size` can only be placed on a struct, enum, union, type alias or impl block",
+            )),
+        }
+    }
+}
+
+impl ToTokens for AnnotatedItem {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        match self {
+            AnnotatedItem::Definition(input) => input.to_tokens(tokens),
+            AnnotatedItem::Alias(alias) => alias.to_tokens(tokens),
+            AnnotatedItem::Impl(block) => block.to_tokens(tokens),
+        }
+    }
+}
@@ -1,7 +1,10 @@
 //! Compile-time type size assertions.
 //!
 //! This crate provides the [`assert_size`] attribute macro for verifying that types
-//! have the expected size in bytes at compile time.
+//! have the expected size in bytes at compile time, and the [`assert_align`] attribute
+//! macro for verifying their alignment. Types defined elsewhere can be checked with the
+//! [`assert_sizes!`] macro. The same assertions can also be derived with
+//! [`derive@AssertSize`], which leaves the annotated type untouched.
 //!
 //! # Quick Start
 //!
@@ -24,26 +27,24 @@
 //! - Documenting expected type sizes for performance-critical code
 //! - Detecting platform-specific size variations
 
+mod args;
+mod c_export;
+mod c_header;
+mod discriminant;
+mod expand;
+mod item;
+mod padding;
+mod repr;
+mod report;
+mod snapshot;
+mod variants;
+
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{
-    DeriveInput, LitInt, Result, parse::{Parse, ParseStream}, parse_macro_input
-};
-
-struct AssertSizeAttributeArgs {
-    desired_size_in_bytes: usize,
-}
+use syn::{DeriveInput, parse_macro_input};
 
-impl Parse for AssertSizeAttributeArgs {
-    fn parse(input: ParseStream) -> Result<Self> {
-        // Parse the input as a single integer literal
-        let lit: LitInt = input.parse()?;
-
-        // Use a base10 conversion to get the integer value
-        let value: usize = lit.base10_parse()?;
-        Ok(AssertSizeAttributeArgs { desired_size_in_bytes: value })
-    }
-}
+use args::{AssertAlignAttributeArgs, AssertSizeAttributeArgs, AssertSizesArgs};
+use item::AnnotatedItem;
 
 /// A compile-time assertion that verifies a type has the expected size in bytes.
 ///
@@ -53,7 +54,46 @@ impl Parse for AssertSizeAttributeArgs {
 ///
 /// # Parameters
 ///
-/// * A single integer literal representing the expected size in bytes
+/// * The expected size in bytes, as an integer literal or any const expression of type
+///   `usize`, or a bound on the size: `<= N`, `< N`, `>= N`, `> N`, or a range such as
+///   `16..=32`, `16..32` or `16..`
+/// * Alternatively, a list of target arms of the form `predicate => N`, where the predicate is
+///   anything accepted by `#[cfg(...)]`, optionally wrapped in `cfg(...)`, or `_` as a
+///   fallback. The first arm matching the target applies
+/// * `same_as = Type` (optional): asserts that the type has the same size as `Type`. The
+///   expected size may then be left out
+/// * `same_layout_as = Type` (optional): like `same_as`, but also asserts the same alignment
+/// * `option_same_size` (optional): additionally asserts that `Option<Self>` is the same
+///   size as the type, i.e. that the type has a niche to store `None` in
+/// * `no_padding` (optional): additionally asserts that the type contains no padding bytes
+/// * `max_variant_ratio = K` (optional): asserts that the payload of no enum variant is more
+///   than `K` times as large as the next largest. The expected size may then be left out
+/// * `discriminant = u8` (optional): asserts that an enum has the given primitive
+///   representation, and so the given discriminant type
+/// * `repr = "C"` (optional): asserts that the type has each of the given `#[repr]` hints,
+///   such as `"C, align(8)"`
+/// * `forbid_repr = "packed"` (optional): asserts that the type has none of the given
+///   `#[repr]` hints
+/// * `report` (optional): generates an associated `LAYOUT` constant describing each field of
+///   a struct or union
+/// * `test` or `test = "dir"` (optional): generates a test that checks the layout of the
+///   type against a snapshot file in `dir`, which defaults to `layouts`
+/// * `snapshot` or `snapshot = "file"` (optional): asserts that the type has the size
+///   recorded in a lockfile, which defaults to `layout.lock`. The expected size may then be
+///   left out
+/// * `c_header = "file.h", c_struct = "name"` (optional): asserts that the type has the
+///   size and field offsets of the C struct or union `name` in the given header. The
+///   expected size may then be left out
+/// * `c_export` or `c_export = "file.h"` (optional): writes a C declaration of a `#[repr(C)]`
+///   struct or union, with a `_Static_assert` of its size, into a header in `OUT_DIR` or
+///   the given file
+/// * `align = M` (optional): additionally asserts that the type is aligned to exactly `M`
+///   bytes, like [`macro@assert_align`]
+/// * `level = "warn"` (optional): reports failed assertions as warnings instead of errors.
+///   Defaults to `"error"`
+/// * `for = [Type, Type => N, ...]` (optional): concrete instantiations to check when the
+///   annotated type is generic. Each entry uses the leading size unless it gives its own
+///   with `=> N`
 ///
 /// # Use Cases
 ///
@@ -69,6 +109,17 @@ impl Parse for AssertSizeAttributeArgs {
 /// evaluated at compile time, so there is zero runtime overhead. Works in both `std` and `no_std`
 /// environments.
 ///
+/// A mismatch is reported on the expected size in the attribute, naming the type and both
+/// sizes:
+///
+/// ```text
+/// error[E0277]: size of `TooSmall` is 2 bytes, but 1 bytes were expected
+///  --> src/lib.rs:4:15
+///   |
+/// 4 | #[assert_size(1)]
+///   |               ^ expected 1 bytes, found 2 bytes
+/// ```
+///
 /// # Examples
 ///
 /// ```
@@ -89,7 +140,10 @@ impl Parse for AssertSizeAttributeArgs {
 ///
 /// ## Compile-Time Failure Example
 ///
-/// ```compile_fail
+// With the `warn` feature, failed assertions compile, so the examples of failures
+// cannot be tested.
+#[cfg_attr(not(feature = "warn"), doc = "```compile_fail")]
+#[cfg_attr(feature = "warn", doc = "```ignore")]
 /// use assert_size_derive::assert_size;
 ///
 /// // This will fail to compile because the actual size is 2 bytes, not 1
@@ -100,24 +154,752 @@ impl Parse for AssertSizeAttributeArgs {
 /// }
 /// ```
 ///
+/// ## Const Expressions
+///
+/// Anywhere a number of bytes is expected, a const expression can be used instead of a
+/// literal, such as a shared constant or arithmetic on other sizes. Mismatch messages
+/// quote the expression alongside its value.
+///
+/// ```
+/// use assert_size_derive::assert_size;
+///
+/// const HEADER_LEN: usize = 8;
+///
+/// #[assert_size(HEADER_LEN + 4)]
+/// #[repr(C)]
+/// struct Packet {
+///     header: [u8; HEADER_LEN],
+///     #[assert_offset(HEADER_LEN)]
+///     payload: u32,
+/// }
+///
+/// #[assert_size(core::mem::size_of::<u64>() * 3)]
+/// struct Triple(u64, u64, u64);
+/// ```
+///
+/// ## Comparing With Other Types
+///
+/// When mirroring a C struct or keeping a new version of a type layout-compatible with the
+/// old one, the expected size is best stated as that of the other type.
+///
+/// ```
+/// use assert_size_derive::assert_size;
+///
+/// #[repr(C)]
+/// struct HeaderV1 {
+///     kind: u32,
+///     length: u32,
+/// }
+///
+/// #[assert_size(same_layout_as = HeaderV1)]
+/// #[repr(C)]
+/// struct HeaderV2 {
+///     kind: u16,
+///     flags: u16,
+///     length: u32,
+/// }
+///
+/// #[assert_size(8, same_as = u64)]
+/// struct Id([u8; 8]);
+/// ```
+///
+/// ## Niche Preservation
+///
+/// Types wrapping `NonNull` or `NonZero*` rely on `Option<Self>` being the same size as
+/// `Self`. `option_same_size` catches changes that lose the niche, which would silently
+/// double the size of an `Option` of a pointer-sized handle.
+///
+/// ```
+/// use assert_size_derive::assert_size;
+/// use core::num::NonZeroU32;
+///
+/// #[assert_size(4, option_same_size)]
+/// struct Handle(NonZeroU32);
+/// ```
+///
+#[cfg_attr(not(feature = "warn"), doc = "```compile_fail")]
+#[cfg_attr(feature = "warn", doc = "```ignore")]
+/// use assert_size_derive::assert_size;
+///
+/// // `u32` has no niche, so `Option<Index>` is 8 bytes
+/// #[assert_size(4, option_same_size)]
+/// struct Index(u32);
+/// ```
+///
+/// ## Padding
+///
+/// Padding bytes are uninitialized, which is a bug for types that are hashed byte-wise or
+/// sent over the wire. `no_padding` asserts that the sizes of the fields of a struct add
+/// up to the size of the struct. Each variant of an enum must fill the enum with its
+/// discriminant and fields, which requires a primitive representation such as
+/// `#[repr(u8)]`, and each field of a union must fill the union.
+///
+/// ```
+/// use assert_size_derive::assert_size;
+///
+/// #[assert_size(8, no_padding)]
+/// #[repr(C)]
+/// struct Header {
+///     kind: u16,
+///     flags: u16,
+///     length: u32,
+/// }
+/// ```
+///
+#[cfg_attr(not(feature = "warn"), doc = "```compile_fail")]
+#[cfg_attr(feature = "warn", doc = "```ignore")]
+/// use assert_size_derive::assert_size;
+///
+/// // 2 bytes of padding follow `flags`
+/// #[assert_size(8, no_padding)]
+/// #[repr(C)]
+/// struct Header {
+///     kind: u16,
+///     length: u32,
+///     flags: u16,
+/// }
+/// ```
+///
+/// ## Enum Variants
+///
+/// The size of an enum is that of its largest variant, which hides the variant responsible
+/// when it grows. The payload of a variant, measured as a tuple of its fields, can be
+/// pinned with a `#[variant_size(N)]` attribute, which accepts the same sizes and bounds as
+/// `assert_size`. `max_variant_ratio = K` asserts that no payload is more than `K` times
+/// the next largest, as flagged by `clippy::large_enum_variant`, and reports the variant
+/// that is. Unit variants count as empty payloads.
+///
+/// ```
+/// use assert_size_derive::assert_size;
+///
+/// #[assert_size(24, max_variant_ratio = 3)]
+/// enum Event {
+///     #[variant_size(<= 24)]
+///     Message(u64, u64, u32),
+///     #[variant_size(8)]
+///     Code(u32, u16),
+///     Pair(u32, u32),
+/// }
+/// ```
+///
+#[cfg_attr(not(feature = "warn"), doc = "```compile_fail")]
+#[cfg_attr(feature = "warn", doc = "```ignore")]
+/// use assert_size_derive::assert_size;
+///
+/// // `Large` is 200 bytes, more than 4 times the 8 bytes of `Small`
+/// #[assert_size(208, max_variant_ratio = 4)]
+/// enum Message {
+///     Small(u64),
+///     Large([u8; 200]),
+/// }
+/// ```
+///
+/// ## Representations
+///
+/// The size of an FFI type only means something with a stable representation, but nothing
+/// stops the `#[repr(C)]` from being deleted. `repr = "..."` requires `#[repr]` hints and
+/// `forbid_repr = "..."` rejects them, such as `C`, `transparent`, `packed`, `align(8)` or
+/// `u8`. A hint without arguments, such as `packed`, also matches it with arguments, such
+/// as `packed(2)`.
+///
+/// ```
+/// use assert_size_derive::assert_size;
+///
+/// #[assert_size(8, repr = "C", forbid_repr = "packed")]
+/// #[repr(C)]
+/// struct Header {
+///     kind: u16,
+///     length: u32,
+/// }
+/// ```
+///
+#[cfg_attr(not(feature = "warn"), doc = "```compile_fail")]
+#[cfg_attr(feature = "warn", doc = "```ignore")]
+/// use assert_size_derive::assert_size;
+///
+/// // The `#[repr(C)]` was removed
+/// #[assert_size(8, repr = "C")]
+/// struct Header {
+///     kind: u16,
+///     length: u32,
+/// }
+/// ```
+///
+/// ## Discriminants
+///
+/// Protocol enums are converted to and from numbers on the wire, so reordering their
+/// variants silently changes the format. `discriminant = u8` asserts the discriminant type
+/// given by `#[repr(u8)]`, and `#[assert_discriminant(N)]` pins the discriminant of a
+/// variant, including variants with fields and those numbered implicitly.
+///
+/// ```
+/// use assert_size_derive::assert_size;
+///
+/// #[assert_size(1, discriminant = u8)]
+/// #[repr(u8)]
+/// enum Opcode {
+///     #[assert_discriminant(0)]
+///     Nop,
+///     #[assert_discriminant(0x10)]
+///     Load = 0x10,
+///     #[assert_discriminant(0x11)]
+///     Store,
+/// }
+/// ```
+///
+#[cfg_attr(not(feature = "warn"), doc = "```compile_fail")]
+#[cfg_attr(feature = "warn", doc = "```ignore")]
+/// use assert_size_derive::assert_size;
+///
+/// // `Load` was moved before `Nop`, so its discriminant is now 0
+/// #[assert_size(1, discriminant = u8)]
+/// #[repr(u8)]
+/// enum Opcode {
+///     #[assert_discriminant(1)]
+///     Load,
+///     #[assert_discriminant(0)]
+///     Nop,
+/// }
+/// ```
+///
+/// ## C Headers
+///
+/// When a type mirrors a struct declared in a C header, `c_header = "file.h"` and
+/// `c_struct = "name"` check it against the header itself, so the two cannot drift apart.
+/// The header is read while the macro expands, from a path relative to the crate's
+/// manifest directory, and the C layout of the struct is computed for the target's ABI.
+/// The type must then have the same size, and each field the offset of the C field with
+/// the same name. `c_struct` is a typedef name or a tag such as `"struct packet"`.
+///
+/// Only the declarations found in typical FFI headers are understood: structs, unions,
+/// enums, typedefs, pointers, including those to incomplete and opaque types, function
+/// pointers, arrays sized by integer expressions of literals and `#define`d constants,
+/// and the types of `<stdint.h>` and `<stddef.h>`. Bit-fields, `long double` and
+/// `#pragma pack` are rejected, and other declarations are skipped.
+///
+/// ```
+/// use assert_size_derive::assert_size;
+///
+/// // `typedef struct packet { uint8_t kind; uint32_t sequence; uint16_t length;
+/// //     const uint8_t *payload; } packet_t;`
+/// #[assert_size(c_header = "tests/include/ffi.h", c_struct = "packet_t")]
+/// #[repr(C)]
+/// struct Packet {
+///     kind: u8,
+///     sequence: u32,
+///     length: u16,
+///     payload: *const u8,
+/// }
+/// ```
+///
+#[cfg_attr(not(feature = "warn"), doc = "```compile_fail")]
+#[cfg_attr(feature = "warn", doc = "```ignore")]
+/// use assert_size_derive::assert_size;
+///
+/// // `length` and `sequence` are swapped
+/// #[assert_size(c_header = "tests/include/ffi.h", c_struct = "packet_t")]
+/// #[repr(C)]
+/// struct Packet {
+///     kind: u8,
+///     length: u16,
+///     sequence: u32,
+///     payload: *const u8,
+/// }
+/// ```
+///
+/// ## Exporting to C
+///
+/// In the other direction, `c_export` writes a C declaration of a `#[repr(C)]` struct or
+/// union into a header while the macro expands, followed by a `_Static_assert` of the
+/// expected size, and of the alignment if `align = M` is given, so the C side checks the
+/// same layout. The expected size must be an integer literal. Without a path, the header
+/// is `assert_size.h` in `OUT_DIR`, which requires a build script; a path is relative to
+/// the crate's manifest directory.
+///
+/// Every type exported to a header gets its own block in it, which is replaced when the
+/// type changes. C has a single namespace for types, so exporting two types with the same
+/// name to one header is an error. Blocks of types that are no longer exported are kept,
+/// so delete the header and rebuild, as with `cargo clean -p my_crate && cargo build`, to
+/// remove them.
+///
+/// Fields map to the `<stdint.h>` types, `core::ffi` types to their C names, and pointers,
+/// references, `NonNull`, arrays and `extern "C" fn` pointers to their C declarators. Any
+/// other type is referred to by name, and so must be exported to the same header before it.
+///
+/// ```ignore
+/// use assert_size_derive::assert_size;
+///
+/// #[assert_size(8, align = 4, c_export = "include/geometry.h")]
+/// #[repr(C)]
+/// struct Point {
+///     x: f32,
+///     y: f32,
+/// }
+/// ```
+///
+/// writes:
+///
+/// ```c
+/// typedef struct Point Point;
+/// struct Point {
+///     float x;
+///     float y;
+/// };
+/// _Static_assert(sizeof(Point) == 8, "Point must be 8 bytes");
+/// _Static_assert(_Alignof(Point) == 4, "Point must be aligned to 4 bytes");
+/// ```
+///
+/// ## Layout Report
+///
+/// `report` generates an associated `const LAYOUT: &'static [FieldLayout]` listing the
+/// name, offset, size and alignment of each field in declaration order, along with the
+/// padding bytes following it in memory. The values are computed with `offset_of!` and
+/// `size_of`, so they are exact for the target being compiled. `FieldLayout` lives in the
+/// `assert-size-layout` crate, which must be added as a dependency.
+///
+/// ```
+/// use assert_size_derive::assert_size;
+///
+/// #[assert_size(8, report)]
+/// #[repr(C)]
+/// struct Header {
+///     kind: u8,
+///     length: u32,
+/// }
+///
+/// for field in Header::LAYOUT {
+///     println!(
+///         "{} at {}: {} bytes + {} padding",
+///         field.name, field.offset, field.size, field.padding_after
+///     );
+/// }
+/// assert_eq!(Header::LAYOUT[0].padding_after, 3);
+/// ```
+///
+/// ## Layout Snapshot Tests
+///
+/// Compile-time failures stop at the first mismatch and show little of the layout. `test`
+/// generates a `#[cfg(test)]` test named after the type, such as `header_layout`, which
+/// checks the size, alignment and field offsets of the type against a snapshot file, and
+/// fails with a diff when the layout drifts or when the snapshot is missing. Snapshots are
+/// stored in the given directory, relative to the crate's manifest directory, and are meant
+/// to be checked in. Running the tests with `ASSERT_SIZE_BLESS=1` records the current
+/// layouts instead.
+///
+/// Each listed instantiation of a generic type is included in the snapshot, while the
+/// fields of enums are left out, since their offsets cannot be measured. The generated test
+/// uses the `assert-size-layout` crate, which must be added as a dev-dependency.
+///
+/// ```
+/// use assert_size_derive::assert_size;
+///
+/// #[assert_size(8, test = "tests/layouts")]
+/// #[repr(C)]
+/// struct Header {
+///     kind: u8,
+///     length: u32,
+/// }
+/// ```
+///
+/// A drifted layout fails the test with a diff such as:
+///
+/// ```text
+/// the layout of `my_crate::Header` does not match the snapshot in
+/// `tests/layouts/my_crate__Header.snap`; run `ASSERT_SIZE_BLESS=1 cargo test` to record
+/// the new layout
+///
+///  Header
+///    size: 8
+///    align: 4
+/// -  kind: offset 0, size 1, align 1, padding 3
+/// +  kind: offset 0, size 2, align 2, padding 2
+///    length: offset 4, size 4, align 4, padding 0
+/// ```
+///
+/// ## Lockfile Snapshots
+///
+/// Maintaining a number in every attribute is tedious across hundreds of types. With
+/// `snapshot`, the expected size is read from a lockfile while the macro expands instead,
+/// keyed by the type's module path and name, and by target:
+///
+/// ```toml
+/// ["x86_64-unknown-linux-gnu"]
+/// cfg = { target_arch = "x86_64", target_os = "linux", target_pointer_width = "64", ... }
+/// "my_crate::net::Header" = 8
+/// ```
+///
+/// Targets are keyed by their triple, and their sizes are checked when compiling for a
+/// target with the recorded `cfg` values, so that targets such as `x86_64-unknown-linux-gnu`,
+/// `-gnux32` and `-musl` are recorded separately. Compiling for a target missing from the
+/// lockfile is an error. Running the tests with `ASSERT_SIZE_BLESS=1` skips the checks,
+/// and the tests generated for each type record its current size for the target they run
+/// on instead. The lockfile path is relative to the crate's manifest
+/// directory, and the generated tests use the `assert-size-layout` crate, which must be
+/// added as a dev-dependency.
+///
+/// ```ignore
+/// use assert_size_derive::assert_size;
+///
+/// #[assert_size(snapshot)]
+/// #[repr(C)]
+/// struct Header {
+///     kind: u8,
+///     length: u32,
+/// }
+///
+/// // Checked against the size of every listed instantiation in `layouts/net.lock`.
+/// #[assert_size(snapshot = "layouts/net.lock", for = [Wrapper<u64>, Wrapper<u8>])]
+/// struct Wrapper<T> {
+///     value: T,
+/// }
+/// ```
+///
+/// ## Warnings Instead of Errors
+///
+/// During a large refactor, it helps to see every size change at once rather than stopping
+/// at the first. With `level = "warn"`, a failed assertion is reported as a warning, and
+/// the `warn` cargo feature does the same for every assertion in the build. The warning
+/// shows both values as the type of an unused result:
+///
+/// ```text
+/// warning: unused result of type `_::SizeOf<Header, _::Expected<8>, _::Found<16>>`
+///  --> src/lib.rs:3:15
+///   |
+/// 3 | #[assert_size(8, level = "warn")]
+///   |               ^
+/// ```
+///
+/// Bounds are shown as `AtMost<N>`, `LessThan<N>`, `AtLeast<N>` or `MoreThan<N>` instead
+/// of `Expected<N>`, and other checks are named after what they measure, such as
+/// `AlignOf` and `OffsetOf`. Checks made while the macro expands, such as those of
+/// `repr`, `forbid_repr` and `discriminant`, are reported as the use of a deprecated
+/// constant, with the error as its note.
+///
+/// ```
+/// use assert_size_derive::assert_size;
+///
+/// #[assert_size(16, align = 8, level = "warn")]
+/// struct Pair {
+///     first: u64,
+///     second: u64,
+/// }
+/// ```
+///
+/// ## Size Bounds
+///
+/// Types with a size budget rather than an exact size can be checked against a bound,
+/// so harmless field changes don't require updating the attribute.
+///
+/// ```
+/// use assert_size_derive::assert_size;
+///
+/// // Fits in a cache line
+/// #[assert_size(<= 64)]
+/// struct Entry {
+///     key: u64,
+///     value: [u8; 24],
+/// }
+///
+/// #[assert_size(16..=32)]
+/// struct Message {
+///     header: u64,
+///     body: [u8; 12],
+/// }
+/// ```
+///
+/// ## Per-Target Sizes
+///
+/// Types holding pointers or `usize` differ in size between targets. Each arm is checked
+/// only on targets matching its `cfg` predicate, and the first matching arm wins. Without a
+/// `_` arm, compiling for a target that no arm matches is an error.
+///
+/// ```
+/// use assert_size_derive::assert_size;
+///
+/// #[assert_size(target_pointer_width = "64" => 24, target_pointer_width = "32" => 12)]
+/// struct Slices {
+///     data: Vec<u8>,
+/// }
+///
+/// #[assert_size(cfg(all(unix, target_pointer_width = "64")) => 16, _ => <= 16)]
+/// struct Handle {
+///     fd: i32,
+///     ptr: *const u8,
+/// }
+/// ```
+///
+/// ## Field Offsets
+///
+/// Struct fields can be pinned to a byte offset with the `#[assert_offset(N)]` helper
+/// attribute, which is checked with `core::mem::offset_of!` and removed from the emitted
+/// item. Tuple-struct fields are checked by index. On generic types, every listed
+/// instantiation must place the field at the same offset.
+///
+/// ```
+/// use assert_size_derive::assert_size;
+///
+/// #[assert_size(16)]
+/// #[repr(C)]
+/// struct WireHeader {
+///     #[assert_offset(0)]
+///     tag: u8,
+///     #[assert_offset(4)]
+///     length: u32,
+///     #[assert_offset(8)]
+///     checksum: u64,
+/// }
+///
+/// #[assert_size(8)]
+/// #[repr(C)]
+/// struct Pair(#[assert_offset(0)] u32, #[assert_offset(4)] u32);
+/// ```
+///
+/// ## Generic Types
+///
+/// A generic type has no single size, so the instantiations to check must be listed with
+/// `for = [...]`. Lifetime parameters do not affect size and need no instantiation.
+///
+/// ```
+/// use assert_size_derive::assert_size;
+///
+/// #[assert_size(8, for = [Wrapper<u64>, Wrapper<u32> => 4, Wrapper<[u8; 3]> => 3])]
+/// struct Wrapper<T> {
+///     value: T,
+/// }
+///
+/// #[assert_size(16, for = [Buffer<16>, Buffer<32> => 32])]
+/// struct Buffer<const N: usize> {
+///     bytes: [u8; N],
+/// }
+///
+/// #[assert_size(8)]
+/// struct Borrowed<'a> {
+///     value: &'a u64,
+/// }
+/// ```
+///
+/// ## Type Aliases and Impl Blocks
+///
+/// On a type alias, the aliased type is checked, which allows concrete instantiations of
+/// generic types to be checked where they are named. On an impl block, the type being
+/// implemented is checked. Options that need the type definition, such as `no_padding`,
+/// `report` and `for = [...]`, are not supported there, and neither are aliases and impl
+/// blocks with type or const parameters. Lifetime parameters are filled in with `'static`.
+///
+/// ```
+/// use assert_size_derive::assert_size;
+///
+/// struct Header<T> {
+///     kind: T,
+///     length: u32,
+/// }
+///
+/// #[assert_size(8)]
+/// type Packet = Header<u16>;
+///
+/// #[assert_size(8, align = 4)]
+/// impl Header<u32> {
+///     fn total_len(&self) -> u32 {
+///         self.length + 8
+///     }
+/// }
+/// ```
+///
 /// # Compatibility
 ///
-/// Works with any type definition: structs, enums, and unions.
+/// Works with any type definition: structs, enums, and unions, as well as type aliases and
+/// impl blocks.
 #[proc_macro_attribute]
 pub fn assert_size(attr: TokenStream, item: TokenStream) -> TokenStream {
     let args = parse_macro_input!(attr as AssertSizeAttributeArgs);
 
+    let mut item = parse_macro_input!(item as AnnotatedItem);
+
+    let assertions = expand::expand_assert_size_item(&args, &mut item)
+        .unwrap_or_else(|err| err.to_compile_error());
+
+    let generated_test_code = quote! {
+        #assertions
+
+        #item
+    };
+
+    generated_test_code.into()
+}
+
+/// Derives the compile-time assertions of [`macro@assert_size`] from a `#[size(...)]` helper
+/// attribute.
+///
+/// An attribute macro re-emits the whole item, which can interact poorly with the order of
+/// other attribute macros and with IDE tooling. The derive leaves the item untouched and
+/// only emits the assertions. The `#[size(...)]` attribute takes the same arguments as
+/// `#[assert_size(...)]`, and fields and enum variants can be pinned with
+/// `#[assert_offset(N)]`, `#[variant_size(N)]` and `#[assert_discriminant(N)]` as well.
+///
+/// # Examples
+///
+/// ```
+/// use assert_size_derive::AssertSize;
+///
+/// #[derive(AssertSize)]
+/// #[size(16, align = 8, no_padding)]
+/// #[repr(C)]
+/// struct Header {
+///     #[assert_offset(0)]
+///     kind: u32,
+///     length: u32,
+///     #[assert_offset(8)]
+///     checksum: u64,
+/// }
+///
+/// #[derive(AssertSize)]
+/// #[size(8, for = [Wrapper<u64>, Wrapper<u32> => 4])]
+/// struct Wrapper<T> {
+///     value: T,
+/// }
+/// ```
+///
+/// ## Compile-Time Failure Example
+///
+#[cfg_attr(not(feature = "warn"), doc = "```compile_fail")]
+#[cfg_attr(feature = "warn", doc = "```ignore")]
+/// use assert_size_derive::AssertSize;
+///
+/// // This will fail to compile because the actual size is 2 bytes, not 1
+/// #[derive(AssertSize)]
+/// #[size(1)]
+/// struct TooSmall {
+///     a: u8,
+///     b: u8,
+/// }
+/// ```
+#[proc_macro_derive(AssertSize, attributes(size, assert_offset, variant_size, assert_discriminant))]
+pub fn derive_assert_size(item: TokenStream) -> TokenStream {
     let input = parse_macro_input!(item as DeriveInput);
 
-    let desired_size_in_bytes = args.desired_size_in_bytes;
-    let type_name = &input.ident;
+    expand::expand_derive_assert_size(&input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+/// A compile-time assertion that verifies a type has the expected alignment in bytes.
+///
+/// This is the alignment counterpart to [`macro@assert_size`], checking
+/// `core::mem::align_of` instead of `core::mem::size_of`. It is useful for types that must
+/// sit on cache-line or DMA boundaries. Like `assert_size`, the check is a const assertion
+/// with zero runtime overhead.
+///
+/// # Parameters
+///
+/// * The expected alignment in bytes, as an integer literal, which must be a power of two,
+///   or any const expression of type `usize`
+/// * `for = [Type, Type => M, ...]` (optional): concrete instantiations to check when the
+///   annotated type is generic, as for `assert_size`
+/// * `level = "warn"` (optional): reports a failed assertion as a warning instead of an
+///   error, as for `assert_size`
+///
+/// # Examples
+///
+/// ```
+/// use assert_size_derive::{assert_align, assert_size};
+///
+/// #[assert_align(64)]
+/// #[repr(C, align(64))]
+/// struct CacheLine {
+///     data: [u8; 64],
+/// }
+///
+/// // Size and alignment can also be checked together.
+/// #[assert_size(16, align = 8)]
+/// struct Pair {
+///     a: u64,
+///     b: u32,
+/// }
+/// ```
+///
+/// ## Compile-Time Failure Example
+///
+#[cfg_attr(not(feature = "warn"), doc = "```compile_fail")]
+#[cfg_attr(feature = "warn", doc = "```ignore")]
+/// use assert_size_derive::assert_align;
+///
+/// // This will fail to compile because the actual alignment is 1 byte, not 4
+/// #[assert_align(4)]
+/// struct Bytes {
+///     data: [u8; 4],
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn assert_align(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(attr as AssertAlignAttributeArgs);
+
+    let input = parse_macro_input!(item as DeriveInput);
+
+    let assertions = expand::expand_assert_align(&args, &input)
+        .unwrap_or_else(|err| err.to_compile_error());
 
     let generated_test_code = quote! {
-        #[allow(unknown_lints, clippy::eq_op)]
-        const _: () = assert!(#desired_size_in_bytes == ::core::mem::size_of::<#type_name>());
+        #assertions
 
         #input
     };
 
     generated_test_code.into()
 }
+
+/// A compile-time assertion on the sizes of types that are defined elsewhere.
+///
+/// [`macro@assert_size`] can only annotate a type definition, which rules out types from
+/// other crates, type aliases and concrete instantiations of generic types such as
+/// `Vec<u8>`. `assert_sizes!` takes a list of `Type => N` entries instead, and can be used
+/// at module scope or inside functions. Like `assert_size`, the checks are const
+/// assertions with zero runtime overhead.
+///
+/// The macro is named `assert_sizes!` rather than `assert_size!` because function-like
+/// and attribute macros share a namespace, so it cannot have the name of the attribute.
+///
+/// # Parameters
+///
+/// Each entry is a type followed by `=>` and either:
+///
+/// * The expected size in bytes, as an integer literal, a const expression or a bound, as
+///   for `assert_size`
+/// * Or, in parentheses, the same arguments as `assert_size`, such as target arms,
+///   `same_as = Type`, `option_same_size` or `align = M`. `no_padding`, `report` and
+///   `for = [...]` need the type definition and are not supported
+///
+/// # Examples
+///
+/// ```
+/// use assert_size_derive::assert_sizes;
+/// use core::num::NonZeroU64;
+///
+/// assert_sizes!(
+///     Vec<u8> => (target_pointer_width = "64" => 24, target_pointer_width = "32" => 12),
+///     NonZeroU64 => (8, option_same_size),
+///     [u32; 4] => (<= 16, align = 4),
+///     usize => (target_pointer_width = "64" => 8, _ => <= 8),
+/// );
+///
+/// fn check() {
+///     assert_sizes!((u8, u16) => 4);
+/// }
+/// ```
+///
+/// ## Compile-Time Failure Example
+///
+#[cfg_attr(not(feature = "warn"), doc = "```compile_fail")]
+#[cfg_attr(feature = "warn", doc = "```ignore")]
+/// use assert_size_derive::assert_sizes;
+///
+/// // This will fail to compile because `u64` is 8 bytes, not 4
+/// assert_sizes!(u64 => 4);
+/// ```
+#[proc_macro]
+pub fn assert_sizes(input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(input as AssertSizesArgs);
+
+    expand::expand_assert_sizes(&args)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
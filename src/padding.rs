@@ -0,0 +1,129 @@
+//! Code generation for the `no_padding` option.
+
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{ToTokens, quote, quote_spanned};
+use syn::{Data, DeriveInput, Error, Result, Type};
+
+use crate::args::Level;
+use crate::expand::{AssertedType, Diagnostic, equality_check};
+use crate::repr::primitive_repr;
+
+/// A part of the annotated type that must be free of padding: the fields of a struct,
+/// the discriminant and fields of an enum variant, or a single field of a union. Each
+/// part must cover the whole type.
+struct Part {
+    field_types: Vec<Type>,
+    message: String,
+    label: &'static str,
+    /// The name of the type shown in warnings, as described in [`Diagnostic`].
+    warning: &'static str,
+}
+
+/// Generates the const assertions for `no_padding`, checking every part of the type for
+/// each of `types`.
+///
+/// The field types may refer to the generic parameters of the annotated type, so their
+/// sizes are summed in an impl of a local trait for the generic type, from which each
+/// instantiation's sum is then read.
+pub(crate) fn no_padding_assertions<V>(
+    input: &DeriveInput,
+    types: &[AssertedType<V>],
+    span: Span,
+    level: Level,
+) -> Result<TokenStream2> {
+    let parts = parts(input, span)?;
+
+    let type_name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let sums = parts.iter().enumerate().map(|(index, part)| {
+        let sum = if part.field_types.is_empty() {
+            quote!(0)
+        } else {
+            let field_types = &part.field_types;
+            quote!(#(::core::mem::size_of::<#field_types>())+*)
+        };
+        quote_spanned! {span=>
+            impl #impl_generics FieldSizes<#index> for #type_name #ty_generics #where_clause {
+                const SUM: usize = #sum;
+            }
+        }
+    });
+
+    let checks = types.iter().flat_map(|asserted| {
+        let ty = &asserted.ty;
+        let span = asserted.span.unwrap_or(span);
+        parts.iter().enumerate().map(move |(index, part)| {
+            equality_check(
+                ty,
+                quote!(::core::mem::size_of::<#ty>()),
+                quote!(<#ty as FieldSizes<#index>>::SUM),
+                &Diagnostic { message: &part.message, label: part.label, warning: part.warning },
+                span,
+                level,
+            )
+        })
+    });
+
+    Ok(quote_spanned! {span=>
+        const _: () = {
+            trait FieldSizes<const PART: usize> {
+                const SUM: usize;
+            }
+
+            #(#sums)*
+            #(#checks)*
+        };
+    })
+}
+
+/// Splits the annotated type into the parts that must each cover the whole type.
+fn parts(input: &DeriveInput, span: Span) -> Result<Vec<Part>> {
+    match &input.data {
+        Data::Struct(data) => Ok(vec![Part {
+            field_types: data.fields.iter().map(|field| field.ty.clone()).collect(),
+            message: "`{Self}` contains padding: its fields add up to {ACTUAL} bytes, but it is {EXPECTED} bytes"
+                .to_owned(),
+            label: "only {ACTUAL} of {EXPECTED} bytes are fields",
+            warning: "FieldSizesOf",
+        }]),
+        Data::Enum(data) => {
+            let Some(discriminant) = primitive_repr(&input.attrs)? else {
+                return Err(Error::new(
+                    span,
+                    "`no_padding` on an enum requires a primitive representation such as `#[repr(u8)]`",
+                ));
+            };
+            let discriminant: Type = syn::parse_quote!(#discriminant);
+
+            Ok(data
+                .variants
+                .iter()
+                .map(|variant| Part {
+                    field_types: std::iter::once(discriminant.clone())
+                        .chain(variant.fields.iter().map(|field| field.ty.clone()))
+                        .collect(),
+                    message: format!(
+                        "variant `{}` of `{{Self}}` contains padding: its discriminant and fields add up to {{ACTUAL}} bytes, but `{{Self}}` is {{EXPECTED}} bytes",
+                        variant.ident
+                    ),
+                    label: "only {ACTUAL} of {EXPECTED} bytes are the discriminant or fields",
+                    warning: "VariantSizeOf",
+                })
+                .collect())
+        }
+        Data::Union(data) => Ok(data
+            .fields
+            .named
+            .iter()
+            .map(|field| Part {
+                field_types: vec![field.ty.clone()],
+                message: format!(
+                    "`{{Self}}` contains padding: field `{}` is {{ACTUAL}} bytes, but the union is {{EXPECTED}} bytes",
+                    field.ident.to_token_stream()
+                ),
+                label: "only {ACTUAL} of {EXPECTED} bytes are this field",
+                warning: "FieldSizeOf",
+            })
+            .collect()),
+    }
+}
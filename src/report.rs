@@ -0,0 +1,137 @@
+//! Code generation for the `report` and `test` options, which both describe the layout of
+//! each field.
+
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote, quote_spanned};
+use syn::{Data, DeriveInput, Error, Field, Fields, Member, Result, ext::IdentExt};
+
+use crate::args::PathOption;
+use crate::expand::{AssertedType, type_to_string};
+
+/// The snapshot directory used by the `test` option unless another one is given.
+const DEFAULT_SNAPSHOT_DIR: &str = "layouts";
+
+/// Generates the associated `LAYOUT` constant listing the layout of each field of the
+/// annotated struct or union.
+pub(crate) fn layout_report(input: &DeriveInput, span: Span) -> Result<TokenStream2> {
+    let Some(fields) = layout_fields(input) else {
+        return Err(Error::new(span, "`report` is only supported on structs and unions"));
+    };
+    Ok(layout_const(input, &fields, span))
+}
+
+/// Generates a test that checks the layout of each asserted type against a snapshot.
+pub(crate) fn layout_test<V>(input: &DeriveInput, types: &[AssertedType<V>], test: &PathOption) -> TokenStream2 {
+    let fields = layout_fields(input).unwrap_or(Fields::Unit);
+    let dir = test
+        .path
+        .as_ref()
+        .map_or_else(|| DEFAULT_SNAPSHOT_DIR.to_owned(), |dir| dir.value());
+    let type_name = input.ident.unraw().to_string();
+    let test_name = format_ident!("{}_layout", snake_case(&type_name), span = test.span);
+
+    let layouts = types.iter().map(|asserted| {
+        let ty = &asserted.ty;
+        let name = type_to_string(ty);
+        let field_layouts = members(&fields).map(|(member, name, _)| {
+            quote! {
+                ::assert_size_layout::FieldLayout::of_field(
+                    #name,
+                    ::core::mem::offset_of!(#ty, #member),
+                    |value: *const #ty| unsafe { ::core::ptr::addr_of!((*value).#member) },
+                )
+            }
+        });
+        quote! {
+            ::assert_size_layout::TypeLayout {
+                name: #name,
+                size: ::core::mem::size_of::<#ty>(),
+                align: ::core::mem::align_of::<#ty>(),
+                fields: &::assert_size_layout::FieldLayout::with_padding(
+                    [#(#field_layouts),*],
+                    ::core::mem::size_of::<#ty>(),
+                ),
+            }
+        }
+    });
+
+    quote_spanned! {test.span=>
+        #[cfg(test)]
+        #[test]
+        fn #test_name() {
+            ::assert_size_layout::check_snapshot(
+                ::core::concat!(::core::env!("CARGO_MANIFEST_DIR"), "/", #dir),
+                ::core::concat!(::core::module_path!(), "::", #type_name),
+                &[#(#layouts),*],
+            );
+        }
+    }
+}
+
+/// The fields of a struct or union, or `None` for an enum, whose fields cannot be measured
+/// with `offset_of!`.
+fn layout_fields(input: &DeriveInput) -> Option<Fields> {
+    match &input.data {
+        Data::Struct(data) => Some(data.fields.clone()),
+        Data::Union(data) => Some(Fields::Named(data.fields.clone())),
+        Data::Enum(_) => None,
+    }
+}
+
+/// The member of each field along with its name, which is its index for tuple-struct
+/// fields.
+fn members(fields: &Fields) -> impl Iterator<Item = (Member, String, &Field)> {
+    fields.iter().enumerate().map(|(index, field)| {
+        let (member, name) = match &field.ident {
+            Some(ident) => (Member::Named(ident.clone()), ident.unraw().to_string()),
+            None => (Member::Unnamed(index.into()), index.to_string()),
+        };
+        (member, name, field)
+    })
+}
+
+fn layout_const(input: &DeriveInput, fields: &Fields, span: Span) -> TokenStream2 {
+    let type_name = &input.ident;
+    let vis = &input.vis;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let field_layouts = members(fields).map(|(member, name, field)| {
+        let ty = &field.ty;
+        quote! {
+            ::assert_size_layout::FieldLayout::new(
+                #name,
+                ::core::mem::offset_of!(Self, #member),
+                ::core::mem::size_of::<#ty>(),
+                ::core::mem::align_of::<#ty>(),
+            )
+        }
+    });
+
+    quote_spanned! {span=>
+        impl #impl_generics #type_name #ty_generics #where_clause {
+            /// The layout of each field on the current target, in declaration order.
+            #vis const LAYOUT: &'static [::assert_size_layout::FieldLayout] =
+                &::assert_size_layout::FieldLayout::with_padding(
+                    [#(#field_layouts),*],
+                    ::core::mem::size_of::<Self>(),
+                );
+        }
+    }
+}
+
+/// Converts a type name such as `HttpHeader` to snake case, as in `http_header`.
+pub(crate) fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut converted = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let previous = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+            if previous.is_lowercase() || previous.is_ascii_digit() || (previous.is_uppercase() && next_is_lower) {
+                converted.push('_');
+            }
+        }
+        converted.extend(c.to_lowercase());
+    }
+    converted
+}
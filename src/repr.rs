@@ -0,0 +1,97 @@
+//! Inspection of the `#[repr]` attributes of the annotated type.
+
+use proc_macro2::TokenStream as TokenStream2;
+use quote::ToTokens;
+use syn::{Attribute, DeriveInput, Error, Ident, Meta, Result, Token, punctuated::Punctuated};
+
+use crate::args::{Level, ReprOption};
+use crate::expand::{combine_errors, failed_check};
+
+/// The primitive integer types an enum can use as its discriminant.
+pub(crate) const PRIMITIVE_REPRS: &[&str] = &[
+    "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+];
+
+/// The repr hints other than the primitive integer types.
+pub(crate) const REPR_HINTS: &[&str] = &["C", "Rust", "transparent", "packed", "align"];
+
+/// Collects the hints given in all `#[repr(...)]` attributes, such as `C` and `align(8)`
+/// in `#[repr(C, align(8))]`.
+fn repr_hints(attrs: &[Attribute]) -> Result<Vec<Meta>> {
+    let mut hints = Vec::new();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("repr")) {
+        hints.extend(attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?);
+    }
+    Ok(hints)
+}
+
+/// Finds the primitive integer representation given in `#[repr(...)]`, such as the `u8`
+/// in `#[repr(C, u8)]`.
+pub(crate) fn primitive_repr(attrs: &[Attribute]) -> Result<Option<Ident>> {
+    Ok(repr_hints(attrs)?.into_iter().rev().find_map(|hint| match hint {
+        Meta::Path(path) => path
+            .get_ident()
+            .filter(|ident| PRIMITIVE_REPRS.iter().any(|repr| ident == repr))
+            .cloned(),
+        _ => None,
+    }))
+}
+
+/// Whether `#[repr(...)]` includes the hint `name`, such as `C`, with or without arguments.
+pub(crate) fn has_repr(attrs: &[Attribute], name: &str) -> Result<bool> {
+    Ok(repr_hints(attrs)?.iter().any(|hint| hint.path().is_ident(name)))
+}
+
+/// Checks that the annotated type has every repr hint in `required` and none in
+/// `forbidden`, reporting a missing hint on the option and a forbidden one on the type's
+/// `#[repr]` attribute, as errors or warnings depending on `level`.
+pub(crate) fn check_repr(
+    input: &DeriveInput,
+    required: Option<&ReprOption>,
+    forbidden: Option<&ReprOption>,
+    level: Level,
+) -> Result<TokenStream2> {
+    let present = repr_hints(&input.attrs)?;
+    let mut errors = Vec::new();
+
+    if let Some(required) = required {
+        for hint in &required.hints {
+            if !present.iter().any(|present| matches(hint, present)) {
+                errors.push(Error::new(
+                    required.span,
+                    format!("`{}` must have `#[repr({})]`", input.ident, hint.to_token_stream()),
+                ));
+            }
+        }
+    }
+    for hint in forbidden.iter().flat_map(|forbidden| &forbidden.hints) {
+        for present in present.iter().filter(|present| matches(hint, present)) {
+            errors.push(Error::new_spanned(
+                present,
+                format!(
+                    "`#[repr({})]` is forbidden on `{}` by `forbid_repr`",
+                    present.to_token_stream(),
+                    input.ident
+                ),
+            ));
+        }
+    }
+
+    match combine_errors(errors) {
+        Ok(()) => Ok(TokenStream2::new()),
+        Err(error) => failed_check(error, level),
+    }
+}
+
+/// Whether the `present` repr hint satisfies `hint`. A hint without arguments, such as
+/// `packed`, is satisfied by the same hint with any arguments, such as `packed(2)`.
+fn matches(hint: &Meta, present: &Meta) -> bool {
+    if hint.path().get_ident() != present.path().get_ident() {
+        return false;
+    }
+    match (hint, present) {
+        (Meta::List(hint), Meta::List(present)) => hint.tokens.to_string() == present.tokens.to_string(),
+        (Meta::List(_), _) => false,
+        _ => true,
+    }
+}
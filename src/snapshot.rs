@@ -0,0 +1,188 @@
+//! Code generation for the `snapshot` option.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use assert_size_layout::lockfile::{BLESS_VAR, Lockfile, blessing};
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote, quote_spanned};
+use syn::{DeriveInput, Error, Ident, Result, ext::IdentExt};
+
+use crate::args::{Level, PathOption};
+use crate::expand::{AssertedType, Diagnostic, equality_check, type_to_string};
+use crate::report::snake_case;
+
+/// The lockfile used by the `snapshot` option unless another one is given.
+const DEFAULT_LOCKFILE: &str = "layout.lock";
+
+/// Generates the checks of each asserted type against the sizes recorded in the lockfile
+/// for every target, along with a test that records the sizes when blessing.
+///
+/// The lockfile is read while expanding the macro, but the module path of the type and the
+/// target are only known to the compiler. The sizes are therefore gated on the `cfg` values
+/// recorded for each target triple, and chosen by comparing the recorded module paths with
+/// `module_path!()`.
+pub(crate) fn snapshot_assertions<V>(
+    input: &DeriveInput,
+    types: &[AssertedType<V>],
+    snapshot: &PathOption,
+    level: Level,
+) -> Result<TokenStream2> {
+    let span = snapshot.span;
+    let relative_path = snapshot
+        .path
+        .as_ref()
+        .map_or_else(|| DEFAULT_LOCKFILE.to_owned(), |path| path.value());
+    let manifest_dir = std::env::var_os("CARGO_MANIFEST_DIR")
+        .ok_or_else(|| Error::new(span, "`snapshot` requires building with Cargo"))?;
+    let path = PathBuf::from(manifest_dir).join(&relative_path);
+    let path_str = path.display().to_string();
+
+    let type_name = input.ident.unraw().to_string();
+    let test_name = format_ident!("{}_size_lock", snake_case(&type_name), span = span);
+    let names: Vec<String> = types.iter().map(|asserted| type_to_string(&asserted.ty)).collect();
+    let sizes = types.iter().zip(&names).map(|(asserted, name)| {
+        let ty = &asserted.ty;
+        quote!((#name, ::core::mem::size_of::<#ty>()))
+    });
+    // Neither environment variables nor files read by a procedural macro are tracked by
+    // the compiler, so they are also read by the generated code to rebuild it on changes.
+    let bless_test = quote_spanned! {span=>
+        const _: ::core::option::Option<&str> = ::core::option_env!(#BLESS_VAR);
+
+        #[cfg(test)]
+        #[test]
+        fn #test_name() {
+            ::assert_size_layout::lockfile::bless_sizes(#path_str, ::core::module_path!(), &[#(#sizes),*]);
+        }
+    };
+    if blessing() {
+        return Ok(bless_test);
+    }
+
+    let lockfile = match fs::read_to_string(&path) {
+        Ok(text) => Lockfile::parse(&text)
+            .map_err(|err| Error::new(span, format!("failed to parse `{}`: {}", relative_path, err)))?,
+        Err(err) if err.kind() == ErrorKind::NotFound => {
+            return Err(Error::new(
+                span,
+                format!(
+                    "`{}` does not exist; run `{}=1 cargo test` to record the sizes of the types using `snapshot`",
+                    relative_path, BLESS_VAR
+                ),
+            ));
+        }
+        Err(err) => return Err(Error::new(span, format!("failed to read `{}`: {}", relative_path, err))),
+    };
+
+    let mut predicates = Vec::new();
+    let mut assertions = TokenStream2::new();
+    for (target, recorded) in &lockfile.targets {
+        if recorded.cfg.is_empty() {
+            return Err(Error::new(
+                span,
+                format!(
+                    "target `{}` in `{}` has no `cfg` values; run `{}=1 cargo test` on it to record them",
+                    target, relative_path, BLESS_VAR
+                ),
+            ));
+        }
+        let mut cfg = Vec::new();
+        for (name, value) in &recorded.cfg {
+            let name = syn::parse_str::<Ident>(name).map_err(|_| {
+                Error::new(span, format!("invalid `cfg` option `{}` for `{}` in `{}`", name, target, relative_path))
+            })?;
+            cfg.push(quote!(#name = #value));
+        }
+        let predicate = quote!(all(#(#cfg),*));
+
+        for (asserted, name) in types.iter().zip(&names) {
+            let recorded: Vec<(&str, usize)> = recorded
+                .sizes
+                .iter()
+                .filter_map(|(key, &size)| {
+                    let module = key.strip_suffix(name.as_str())?.strip_suffix("::")?;
+                    Some((module, size))
+                })
+                .collect();
+            let check = recorded_size_check(asserted, name, &recorded, target, &relative_path, span, level);
+            assertions.extend(quote! {
+                #[cfg(#predicate)]
+                #check
+            });
+        }
+        predicates.push(predicate);
+    }
+
+    let message = format!(
+        "`{}` has no sizes for the current target; run `{}=1 cargo test` to record them",
+        relative_path, BLESS_VAR
+    );
+    let tracked_lockfile = quote_spanned! {span=>
+        const _: &[u8] = ::core::include_bytes!(#path_str);
+
+        #[cfg(not(any(#(#predicates),*)))]
+        ::core::compile_error!(#message);
+    };
+
+    Ok(quote!(#bless_test #tracked_lockfile #assertions))
+}
+
+/// Generates the check of a single type against the sizes `recorded` for it on `target`,
+/// by module path.
+fn recorded_size_check<V>(
+    asserted: &AssertedType<V>,
+    name: &str,
+    recorded: &[(&str, usize)],
+    target: &str,
+    lockfile: &str,
+    span: Span,
+    level: Level,
+) -> TokenStream2 {
+    let missing = format!(
+        "`{}` has no size for `{}` on `{}`; run `{}=1 cargo test` to record it",
+        lockfile, name, target, BLESS_VAR
+    );
+    if recorded.is_empty() {
+        return quote_spanned!(span=> ::core::compile_error!(#missing););
+    }
+
+    let modules = recorded.iter().map(|(module, _)| module);
+    let sizes = recorded.iter().map(|(_, size)| size);
+    let ty = &asserted.ty;
+    let check = equality_check(
+        ty,
+        quote_spanned!(span=> #(if module_is(#modules) { #sizes } else)* { ::core::panic!(#missing) }),
+        quote!(::core::mem::size_of::<#ty>()),
+        &Diagnostic {
+            message: &format!("size of `{{Self}}` is {{ACTUAL}} bytes, but {{EXPECTED}} bytes were recorded in `{}`", lockfile),
+            label: "expected {EXPECTED} bytes, found {ACTUAL} bytes",
+            warning: "RecordedSizeOf",
+        },
+        span,
+        level,
+    );
+
+    quote_spanned! {span=>
+        const _: () = {
+            /// Whether the type is defined in the module at `path`.
+            const fn module_is(path: &str) -> bool {
+                let (actual, path) = (::core::module_path!().as_bytes(), path.as_bytes());
+                if actual.len() != path.len() {
+                    return false;
+                }
+                let mut i = 0;
+                while i < actual.len() {
+                    if actual[i] != path[i] {
+                        return false;
+                    }
+                    i += 1;
+                }
+                true
+            }
+
+            #check
+        };
+    }
+}
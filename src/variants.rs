@@ -0,0 +1,175 @@
+//! Code generation for the `#[variant_size(N)]` helper attribute and the
+//! `max_variant_ratio` option, which check the payloads of enum variants.
+
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{ToTokens, quote, quote_spanned};
+use syn::{Data, DeriveInput, Error, Ident, Result, parse::Parse};
+
+use crate::args::{BoundOp, Expectation, Expected, Level};
+use crate::expand::{AssertedType, Diagnostic, combine_errors, comparison_check, expectation_check};
+
+/// The name of the helper attribute that pins the payload size of an enum variant.
+const VARIANT_SIZE: &str = "variant_size";
+
+/// An expected payload size taken from a `#[variant_size(N)]` helper attribute.
+pub(crate) struct VariantSize {
+    /// The index of the variant in declaration order.
+    index: usize,
+    desired_size_in_bytes: Expectation,
+}
+
+/// Strips every `#[variant_size(N)]` helper attribute from the variants of `input` and
+/// returns the payload sizes they expect.
+pub(crate) fn take_variant_sizes(input: &mut DeriveInput) -> Result<Vec<VariantSize>> {
+    Ok(take_variant_attributes(input, VARIANT_SIZE)?
+        .into_iter()
+        .map(|(index, desired_size_in_bytes)| VariantSize { index, desired_size_in_bytes })
+        .collect())
+}
+
+/// Strips every helper attribute called `name` from the variants of `input`, and returns
+/// the index of each variant that had one along with the attribute's parsed argument.
+pub(crate) fn take_variant_attributes<T: Parse>(input: &mut DeriveInput, name: &str) -> Result<Vec<(usize, T)>> {
+    let Data::Enum(data) = &mut input.data else {
+        return Ok(Vec::new());
+    };
+
+    let mut values = Vec::new();
+    let mut errors = Vec::new();
+    for (index, variant) in data.variants.iter_mut().enumerate() {
+        let (helpers, attrs) = variant.attrs.drain(..).partition(|attr| attr.path().is_ident(name));
+        variant.attrs = attrs;
+
+        let mut helpers = helpers.into_iter();
+        let Some(helper) = helpers.next() else {
+            continue;
+        };
+        if let Some(duplicate) = helpers.next() {
+            errors.push(Error::new_spanned(duplicate, format!("duplicate `{}` attribute", name)));
+            continue;
+        }
+
+        match helper.parse_args() {
+            Ok(value) => values.push((index, value)),
+            Err(err) => errors.push(err),
+        }
+    }
+
+    combine_errors(errors)?;
+    Ok(values)
+}
+
+/// Generates the const assertions for the `#[variant_size(N)]` attributes of the variants
+/// and for `max_variant_ratio`, for each of `types`.
+///
+/// The payload of a variant is measured as a tuple of its fields. Like the fields summed
+/// by `no_padding`, they may refer to the generic parameters of the enum, so the size of
+/// each payload is read from an impl of a local trait for the generic type.
+pub(crate) fn variant_assertions<V>(
+    input: &DeriveInput,
+    types: &[AssertedType<V>],
+    sizes: &[VariantSize],
+    max_variant_ratio: Option<&Expected>,
+    level: Level,
+) -> Result<TokenStream2> {
+    let Data::Enum(data) = &input.data else {
+        let span = max_variant_ratio.map_or_else(Span::call_site, |ratio| ratio.span);
+        return Err(Error::new(span, "`max_variant_ratio` is only supported on enums"));
+    };
+    let variants: Vec<&Ident> = data.variants.iter().map(|variant| &variant.ident).collect();
+    if let Some(ratio) = max_variant_ratio {
+        if variants.len() < 2 {
+            return Err(Error::new(
+                ratio.span,
+                "`max_variant_ratio` requires an enum with at least two variants",
+            ));
+        }
+    }
+
+    let type_name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let payloads = data.variants.iter().enumerate().map(|(index, variant)| {
+        let field_types = variant.fields.iter().map(|field| &field.ty);
+        quote! {
+            impl #impl_generics VariantPayload<#index> for #type_name #ty_generics #where_clause {
+                const SIZE: usize = ::core::mem::size_of::<(#(#field_types,)*)>();
+            }
+        }
+    });
+
+    let mut checks = TokenStream2::new();
+    for asserted in types {
+        let ty = &asserted.ty;
+        let payload = |index: usize| quote!(<#ty as VariantPayload<#index>>::SIZE);
+
+        for size in sizes {
+            let subject = format!("payload of variant `{}` in `{{Self}}`", variants[size.index]);
+            checks.extend(expectation_check(
+                ty,
+                &size.desired_size_in_bytes,
+                payload(size.index),
+                &subject,
+                "PayloadSizeOf",
+                |expected| asserted.span.unwrap_or(expected.span),
+                level,
+            ));
+        }
+
+        // The largest payload is more than `K` times the next largest exactly when some
+        // payload is more than `K` times the largest of the others, which names the variant.
+        let Some(ratio) = max_variant_ratio else {
+            continue;
+        };
+        let ratio_description = match ratio.literal {
+            Some(literal) => literal.to_string(),
+            None => format!("`{}`", ratio.expr.to_token_stream()),
+        };
+        let all_payloads: Vec<TokenStream2> = (0..variants.len()).map(payload).collect();
+        let ratio = &ratio.expr;
+        for (index, variant) in variants.iter().enumerate() {
+            let message = format!(
+                "payload of variant `{}` in `{{Self}}` is {{ACTUAL}} bytes, more than {} times the next largest: it must be at most {{BOUND}} bytes",
+                variant, ratio_description
+            );
+            checks.extend(comparison_check(
+                ty,
+                BoundOp::LessOrEqual,
+                &quote!(largest_other(&[#(#all_payloads),*], #index).saturating_mul(#ratio)),
+                &payload(index),
+                &Diagnostic {
+                    message: &message,
+                    label: "expected at most {BOUND} bytes, found {ACTUAL} bytes",
+                    warning: "PayloadSizeOf",
+                },
+                asserted.span.unwrap_or(variant.span()),
+                level,
+            ));
+        }
+    }
+
+    let span = max_variant_ratio.map_or_else(Span::call_site, |ratio| ratio.span);
+    Ok(quote_spanned! {span=>
+        const _: () = {
+            trait VariantPayload<const VARIANT: usize> {
+                const SIZE: usize;
+            }
+
+            /// The largest of `sizes` other than the one at `skip`.
+            #[allow(dead_code)]
+            const fn largest_other(sizes: &[usize], skip: usize) -> usize {
+                let mut largest = 0;
+                let mut i = 0;
+                while i < sizes.len() {
+                    if i != skip && sizes[i] > largest {
+                        largest = sizes[i];
+                    }
+                    i += 1;
+                }
+                largest
+            }
+
+            #(#payloads)*
+            #checks
+        };
+    })
+}
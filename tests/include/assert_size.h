@@ -0,0 +1,40 @@
+/* C declarations of the types exported by `#[assert_size(c_export)]`. Generated; do not edit. */
+#ifndef ASSERT_SIZE_H
+#define ASSERT_SIZE_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+/* begin ExportedPoint */
+typedef struct ExportedPoint ExportedPoint;
+struct ExportedPoint {
+    float x;
+    float y;
+};
+_Static_assert(sizeof(ExportedPoint) == 8, "ExportedPoint must be 8 bytes");
+_Static_assert(_Alignof(ExportedPoint) == 4, "ExportedPoint must be aligned to 4 bytes");
+/* end ExportedPoint */
+
+/* begin ExportedValue */
+typedef union ExportedValue ExportedValue;
+union ExportedValue {
+    int64_t as_int;
+    uint8_t bytes[16];
+};
+_Static_assert(sizeof(ExportedValue) == 16, "ExportedValue must be 16 bytes");
+/* end ExportedValue */
+
+/* begin ExportedShape */
+typedef struct ExportedShape ExportedShape;
+struct ExportedShape {
+    ExportedPoint origin;
+    uint8_t corners[4][2];
+    ExportedValue value;
+    const char *label;
+    bool (*on_draw)(ExportedShape *, uint32_t);
+};
+_Static_assert(sizeof(ExportedShape) == 48, "ExportedShape must be 48 bytes");
+/* end ExportedShape */
+
+#endif /* ASSERT_SIZE_H */
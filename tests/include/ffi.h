@@ -0,0 +1,54 @@
+/* Declarations shared with C, checked by the `c_header` tests. */
+#ifndef FFI_H
+#define FFI_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#define NAME_LEN 16
+#define SLOTS 4 /* number of slots */
+#define SLOT_BYTES (SLOTS * 2) // bytes per slot pair
+#define DEFAULT_SCALE 1.5f
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+enum packet_kind { PACKET_DATA, PACKET_ACK };
+
+typedef struct packet {
+    uint8_t kind;
+    uint32_t sequence;
+    uint16_t length;
+    const uint8_t *payload;
+} packet_t;
+
+struct sensor {
+    char name[NAME_LEN];
+    double reading;
+    enum packet_kind kind;
+    void (*on_change)(struct sensor *sensor, double reading);
+    size_t samples;
+};
+
+typedef union {
+    int32_t as_int;
+    float as_float;
+    uint8_t bytes[4];
+} value_t;
+
+struct ring {
+    uint16_t slots[SLOTS];
+    uint8_t spare[SLOT_BYTES + 1];
+    float scale;
+};
+
+static const double ring_ratio = 2.5e-3;
+
+int packet_send(const packet_t *packet);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -1,5 +1,5 @@
 #![allow(unused)]
-use assert_size_derive::assert_size;
+use assert_size_derive::{AssertSize, assert_align, assert_size, assert_sizes};
 
 // Basic struct tests
 #[assert_size(2)]
@@ -29,6 +29,75 @@ enum LargeEnum {
     Variant2(u32),
 }
 
+// Variant size tests
+#[assert_size(16, max_variant_ratio = 2)]
+enum SizedVariants {
+    #[variant_size(8)]
+    Variant1(u64),
+    #[variant_size(<= 4)]
+    Variant2(u32),
+}
+
+#[assert_size(24, max_variant_ratio = 3)]
+enum VariantBudget {
+    #[variant_size(16..=24)]
+    Message(u64, u64, u32),
+    #[variant_size(8)]
+    Code(u32, u16),
+    Empty,
+    Pair(u32, u32),
+}
+
+#[assert_size(16, max_variant_ratio = 2, for = [GenericVariants<u64>, GenericVariants<u32> => 8])]
+enum GenericVariants<T> {
+    #[variant_size(<= 8)]
+    Value(T),
+    Fallback(u32),
+}
+
+#[derive(AssertSize)]
+#[size(max_variant_ratio = 2)]
+enum DerivedVariants {
+    #[variant_size(4)]
+    Small(u32),
+    Large(u32, u16),
+}
+
+// Discriminant tests
+#[assert_size(1, discriminant = u8)]
+#[repr(u8)]
+enum Opcode {
+    #[assert_discriminant(0)]
+    Nop,
+    #[assert_discriminant(1)]
+    Load,
+    #[assert_discriminant(0x10)]
+    Store = 0x10,
+    #[assert_discriminant(0x11)]
+    Jump,
+}
+
+#[assert_size(4, discriminant = i16)]
+#[repr(i16)]
+enum SignedFrame {
+    #[assert_discriminant(-1)]
+    Invalid = -1,
+    #[assert_discriminant(0)]
+    Empty,
+    #[assert_discriminant(255)]
+    Data(u8) = 255,
+}
+
+#[derive(AssertSize)]
+#[size(2, discriminant = u8)]
+#[repr(u8)]
+enum DerivedDiscriminants {
+    #[assert_discriminant(0)]
+    Ping,
+    #[assert_discriminant(1)]
+    Pong(u8),
+}
+
 // Union tests
 #[assert_size(8)]
 union MyUnion {
@@ -82,6 +151,42 @@ struct ConcretePairU8U8 {
     inner: GenericPair<u8, u8>,
 }
 
+// Generic types - test with instantiation lists
+#[assert_size(8, for = [AnnotatedGeneric<u64>, AnnotatedGeneric<u8> => 1])]
+struct AnnotatedGeneric<T> {
+    value: T,
+}
+
+#[assert_size(16, for = [AnnotatedPair<u64, u64>, AnnotatedPair<u8, u8> => 2, AnnotatedPair<u8, u32> => 8,])]
+struct AnnotatedPair<T, U> {
+    first: T,
+    second: U,
+}
+
+#[assert_size(4, for = [ConstGeneric<4>, ConstGeneric<{ 2 + 2 }>, ConstGeneric<0> => 0])]
+struct ConstGeneric<const N: usize> {
+    bytes: [u8; N],
+}
+
+#[assert_size(16, for = [Mixed<'static, u32, 2>, Mixed<'_, u8, 8> => 16])]
+struct Mixed<'a, T, const N: usize> {
+    values: &'a [T; N],
+    count: usize,
+}
+
+#[assert_size(8)]
+struct LifetimeOnly<'a> {
+    value: &'a u64,
+}
+
+#[assert_size(8, for = [self::WhereClause<u64>])]
+struct WhereClause<T>
+where
+    T: Copy,
+{
+    value: T,
+}
+
 // Edge cases
 #[assert_size(1)]
 struct SingleByte(u8);
@@ -96,3 +201,652 @@ struct NestedStruct {
     byte: u8,
     // 2 bytes from MyData + 5 bytes padding + 16 bytes for [u64; 2] + 1 byte = 24
 }
+
+// Alignment tests
+#[assert_size(16, align = 8)]
+struct AlignedPair {
+    a: u64,
+    b: u32,
+}
+
+#[assert_size(64, align = 64)]
+#[repr(C, align(64))]
+struct CacheLine {
+    data: [u8; 64],
+}
+
+#[assert_align(1)]
+#[repr(C, packed)]
+struct PackedAlign {
+    a: u8,
+    b: u64,
+}
+
+#[assert_align(4)]
+union AlignedUnion {
+    byte: u8,
+    word: u32,
+}
+
+#[assert_align(8, for = [GenericAlign<u64>, GenericAlign<u16> => 2])]
+struct GenericAlign<T> {
+    value: T,
+}
+
+#[assert_size(8, align = 8, for = [AlignedGeneric<u64>, AlignedGeneric<i64>])]
+struct AlignedGeneric<T> {
+    value: T,
+}
+
+// Field offset tests
+#[assert_size(16)]
+#[repr(C)]
+struct WireHeader {
+    #[assert_offset(0)]
+    tag: u8,
+    #[assert_offset(4)]
+    length: u32,
+    #[assert_offset(8)]
+    checksum: u64,
+}
+
+#[assert_size(13)]
+#[repr(C, packed)]
+struct PackedHeader {
+    #[assert_offset(0)]
+    tag: u8,
+    #[assert_offset(1)]
+    length: u32,
+    #[assert_offset(5)]
+    checksum: u64,
+}
+
+#[assert_size(8)]
+#[repr(C)]
+struct TupleOffsets(#[assert_offset(0)] u16, u16, #[assert_offset(4)] u32);
+
+#[assert_size(16, for = [GenericOffsets<u64>, GenericOffsets<u32> => 8])]
+#[repr(C)]
+struct GenericOffsets<T> {
+    #[assert_offset(0)]
+    tag: u8,
+    value: T,
+}
+
+// Size bound tests
+#[assert_size(<= 64)]
+struct CacheLineBudget {
+    key: u64,
+    value: [u8; 24],
+}
+
+#[assert_size(< 3)]
+struct BelowThree(u16);
+
+#[assert_size(>= 2)]
+struct AtLeastTwo(u16);
+
+#[assert_size(> 1)]
+struct AboveOne(u16);
+
+#[assert_size(16..=32)]
+struct InclusiveRange {
+    header: u64,
+    body: [u8; 12],
+}
+
+#[assert_size(1..3)]
+struct ExclusiveRange(u16);
+
+#[assert_size(2..)]
+struct OpenRange(u16);
+
+#[assert_size(..=2)]
+struct InclusiveUpTo(u16);
+
+#[assert_size(<= 8, for = [BoundedGeneric<u64>, BoundedGeneric<[u64; 2]> => 16..=16, BoundedGeneric<u8> => 1])]
+struct BoundedGeneric<T> {
+    value: T,
+}
+
+// Per-target size tests
+#[assert_size(target_pointer_width = "64" => 24, target_pointer_width = "32" => 12)]
+struct PointerWidthArms {
+    data: Vec<u8>,
+}
+
+#[assert_size(target_pointer_width = "64" => 16, _ => 8, align = 8)]
+struct FallbackArm(usize, u64);
+
+#[assert_size(cfg(all(target_pointer_width = "64", target_pointer_width = "32")) => 1, _ => 2)]
+struct FirstMatchingArm(u16);
+
+#[assert_size(not(target_pointer_width = "16") => 4..=4, _ => 2)]
+struct PredicateArm(u32);
+
+#[assert_size(target_pointer_width = "64" => 16, _ => 8, for = [PerTargetGeneric<u8>, PerTargetGeneric<u16>, PerTargetGeneric<[u8; 0]> => 8..=8])]
+struct PerTargetGeneric<T> {
+    value: T,
+    ptr: *const u8,
+}
+
+// Const expression tests
+const HEADER_LEN: usize = 8;
+const CACHE_LINE: usize = 64;
+
+const fn words(count: usize) -> usize {
+    count * core::mem::size_of::<usize>()
+}
+
+#[assert_size(HEADER_LEN + 4, align = core::mem::align_of::<u32>())]
+#[repr(C)]
+struct ExprPacket {
+    header: [u8; HEADER_LEN],
+    #[assert_offset(HEADER_LEN)]
+    payload: u32,
+}
+
+#[assert_size(core::mem::size_of::<u64>() * 3)]
+struct ExprTriple(u64, u64, u64);
+
+#[assert_size(words(2))]
+struct ExprWords(usize, usize);
+
+#[assert_size({ 2 + 2 })]
+struct ExprBlock(u32);
+
+#[assert_size(<= CACHE_LINE / 2)]
+struct ExprBound([u8; 32]);
+
+#[assert_size(HEADER_LEN..=CACHE_LINE)]
+struct ExprRange([u8; 16]);
+
+#[assert_size(target_pointer_width = "64" => words(1), _ => HEADER_LEN / 2)]
+struct ExprArm(usize);
+
+#[assert_size(HEADER_LEN, for = [ExprGeneric<u64>, ExprGeneric<u16> => HEADER_LEN / 4])]
+struct ExprGeneric<T> {
+    value: T,
+}
+
+// Comparison with other types
+#[repr(C)]
+struct HeaderV1 {
+    kind: u32,
+    length: u32,
+}
+
+#[assert_size(same_layout_as = HeaderV1)]
+#[repr(C)]
+struct HeaderV2 {
+    kind: u16,
+    flags: u16,
+    length: u32,
+}
+
+#[assert_size(same_as = HeaderV1)]
+struct SameSizeOnly([u8; 8]);
+
+#[assert_size(8, same_as = u64, align = 1)]
+struct SameAsPrimitive([u8; 8]);
+
+#[assert_size(same_as = GenericPair<u32, u32>, for = [SameAsGeneric<u64>, SameAsGeneric<i64>])]
+struct SameAsGeneric<T> {
+    value: T,
+}
+
+#[assert_size(same_layout_as = [u64; 2], align = 8)]
+struct SameLayoutAsArray(u64, u32);
+
+// Niche preservation tests
+#[assert_size(4, option_same_size)]
+struct NonZeroHandle(core::num::NonZeroU32);
+
+#[assert_size(target_pointer_width = "64" => 8, target_pointer_width = "32" => 4, option_same_size)]
+#[cfg_attr(target_pointer_width = "64", assert_align(8))]
+#[cfg_attr(target_pointer_width = "32", assert_align(4))]
+struct PointerHandle(core::ptr::NonNull<u8>);
+
+#[assert_size(1, option_same_size)]
+#[repr(u8)]
+enum NicheEnum {
+    First,
+    Second,
+}
+
+#[assert_size(
+    target_pointer_width = "64" => 8,
+    target_pointer_width = "32" => 4,
+    option_same_size,
+    for = [NicheGeneric<Box<u8>>, NicheGeneric<&'static u64>]
+)]
+struct NicheGeneric<T> {
+    value: T,
+}
+
+// Padding tests
+#[assert_size(8, no_padding)]
+#[repr(C)]
+struct NoPaddingHeader {
+    kind: u16,
+    flags: u16,
+    length: u32,
+}
+
+#[assert_size(15, no_padding)]
+#[repr(C, packed)]
+struct NoPaddingPacked {
+    data: [u8; 11],
+    ident: u32,
+}
+
+#[assert_size(0, no_padding)]
+struct NoPaddingEmpty;
+
+#[assert_size(8, no_padding)]
+#[repr(u32)]
+enum NoPaddingEnum {
+    Word(u32),
+    Halves(u16, u16),
+    Bytes([u8; 4]),
+}
+
+#[assert_size(1, no_padding)]
+#[repr(u8)]
+enum NoPaddingFieldless {
+    On,
+    Off,
+}
+
+#[assert_size(8, no_padding)]
+union NoPaddingUnion {
+    unsigned: u64,
+    bytes: [u8; 8],
+}
+
+#[assert_size(8, no_padding, for = [NoPaddingGeneric<'static, u32, 1>, NoPaddingGeneric<'static, u8, 4>])]
+#[repr(C)]
+struct NoPaddingGeneric<'a, T: Copy, const N: usize>
+where
+    T: 'a,
+{
+    values: [T; N],
+    marker: core::marker::PhantomData<&'a T>,
+    rest: [u8; 4],
+}
+
+// Repr verification tests
+#[assert_size(8, repr = "C", forbid_repr = "packed")]
+#[repr(C)]
+struct FfiHeader {
+    kind: u16,
+    length: u32,
+}
+
+#[assert_size(16, repr = "C, align(16)")]
+#[repr(C, align(16))]
+struct FfiAligned {
+    value: u64,
+}
+
+#[assert_size(4, repr = "transparent")]
+#[repr(transparent)]
+struct FfiHandle(u32);
+
+#[assert_size(1, repr = "u8", forbid_repr = "C")]
+#[repr(u8)]
+enum FfiStatus {
+    Ok,
+    Failed,
+}
+
+#[derive(AssertSize)]
+#[size(6, repr = "packed", forbid_repr = "align")]
+#[repr(C, packed(2))]
+struct FfiPacked {
+    kind: u16,
+    length: u32,
+}
+
+// C header tests
+#[assert_size(c_header = "tests/include/ffi.h", c_struct = "packet_t")]
+#[repr(C)]
+struct Packet {
+    kind: u8,
+    sequence: u32,
+    length: u16,
+    payload: *const u8,
+}
+
+#[assert_size(c_header = "tests/include/ffi.h", c_struct = "struct sensor", repr = "C")]
+#[repr(C)]
+struct Sensor {
+    name: [core::ffi::c_char; 16],
+    reading: f64,
+    kind: core::ffi::c_int,
+    on_change: Option<extern "C" fn(*mut Sensor, f64)>,
+    samples: usize,
+}
+
+#[assert_size(4, c_header = "tests/include/ffi.h", c_struct = "value_t")]
+#[repr(C)]
+union Value {
+    as_int: i32,
+    as_float: f32,
+    bytes: [u8; 4],
+}
+
+#[assert_size(c_header = "tests/include/ffi.h", c_struct = "struct ring")]
+#[repr(C)]
+struct Ring {
+    slots: [u16; 4],
+    spare: [u8; 9],
+    scale: f32,
+}
+
+// C export tests
+#[assert_size(8, align = 4, c_export)]
+#[repr(C)]
+struct ExportedPoint {
+    x: f32,
+    y: f32,
+}
+
+#[assert_size(16, c_export)]
+#[repr(C)]
+union ExportedValue {
+    as_int: i64,
+    bytes: [u8; 16],
+}
+
+#[cfg(target_pointer_width = "64")]
+#[assert_size(48, c_export)]
+#[repr(C)]
+struct ExportedShape {
+    origin: ExportedPoint,
+    corners: [[u8; 2]; 4],
+    value: ExportedValue,
+    label: *const core::ffi::c_char,
+    on_draw: Option<extern "C" fn(*mut ExportedShape, u32) -> bool>,
+}
+
+// The header is generated in `OUT_DIR` and compared with the one in `tests/include`,
+// which is rewritten by `ASSERT_SIZE_BLESS=1 cargo test`.
+#[cfg(target_pointer_width = "64")]
+#[test]
+fn c_export_writes_declarations() {
+    let header = std::fs::read_to_string(concat!(env!("OUT_DIR"), "/assert_size.h")).unwrap();
+    let golden = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/include/assert_size.h");
+    if assert_size_layout::lockfile::blessing() {
+        std::fs::write(golden, &header).unwrap();
+    } else {
+        assert_eq!(header, std::fs::read_to_string(golden).unwrap());
+    }
+}
+
+// Layout report tests
+use assert_size_layout::FieldLayout;
+
+#[assert_size(16, report)]
+#[repr(C)]
+pub struct ReportHeader {
+    kind: u8,
+    length: u32,
+    checksum: u64,
+}
+
+#[assert_size(16, report)]
+#[repr(C)]
+struct ReportTuple(u16, u64);
+
+#[assert_size(8, report)]
+union ReportUnion {
+    unsigned: u64,
+    bytes: [u8; 4],
+}
+
+#[assert_size(4, report, for = [ReportGeneric<u8>, ReportGeneric<u16>])]
+#[repr(C)]
+struct ReportGeneric<T> {
+    value: T,
+    tail: u16,
+}
+
+#[test]
+fn report_lists_fields_with_padding() {
+    assert_eq!(
+        ReportHeader::LAYOUT,
+        [
+            FieldLayout { name: "kind", offset: 0, size: 1, align: 1, padding_after: 3 },
+            FieldLayout { name: "length", offset: 4, size: 4, align: 4, padding_after: 0 },
+            FieldLayout { name: "checksum", offset: 8, size: 8, align: 8, padding_after: 0 },
+        ]
+    );
+}
+
+#[test]
+fn report_names_tuple_fields_by_index() {
+    let names: Vec<_> = ReportTuple::LAYOUT.iter().map(|field| field.name).collect();
+    assert_eq!(names, ["0", "1"]);
+    assert_eq!(ReportTuple::LAYOUT[0].padding_after, 6);
+}
+
+#[test]
+fn report_measures_union_fields_to_the_end() {
+    assert_eq!(ReportUnion::LAYOUT[0].padding_after, 0);
+    assert_eq!(ReportUnion::LAYOUT[1].padding_after, 4);
+}
+
+#[test]
+fn report_is_generic() {
+    assert_eq!(ReportGeneric::<u8>::LAYOUT[0].padding_after, 1);
+    assert_eq!(ReportGeneric::<u16>::LAYOUT[0].padding_after, 0);
+}
+
+// Function-like macro tests
+type Pair = (u32, u16);
+
+assert_sizes!(
+    u64 => 8,
+    Vec<u8> => (target_pointer_width = "64" => 24, target_pointer_width = "32" => 12),
+    Pair => (8, align = 4),
+    &'static str => (target_pointer_width = "64" => 16, target_pointer_width = "32" => 8, same_as = &'static [u8]),
+    core::num::NonZeroU32 => (4, option_same_size),
+    [u8; HEADER_LEN] => HEADER_LEN,
+    (u8, u8) => (HEADER_LEN - 6),
+    String => (target_pointer_width = "64" => <= 32, target_pointer_width = "32" => <= 16),
+    usize => (target_pointer_width = "64" => 8, _ => <= 8),
+    GenericStruct<u32> => (same_layout_as = u32),
+);
+
+#[test]
+fn assert_sizes_inside_function() {
+    assert_sizes!(u32 => 4, (u8, u16) => (<= 4));
+}
+
+// Derive tests
+#[derive(AssertSize, Clone, Copy)]
+#[size(2)]
+struct DerivedBasic {
+    a: u8,
+    b: u8,
+}
+
+#[derive(AssertSize)]
+#[size(16, align = 8, no_padding, report)]
+#[repr(C)]
+struct DerivedHeader {
+    #[assert_offset(0)]
+    kind: u32,
+    #[assert_offset(4)]
+    length: u32,
+    checksum: u64,
+}
+
+#[derive(AssertSize)]
+#[size(<= 16, for = [DerivedGeneric<u8>, DerivedGeneric<u64> => 16])]
+struct DerivedGeneric<T> {
+    value: T,
+    extra: u64,
+}
+
+#[derive(AssertSize)]
+#[size(target_pointer_width = "64" => 16, _ => 8)]
+enum DerivedEnum {
+    Pointer(*const u8),
+    Empty,
+}
+
+#[test]
+fn derive_leaves_item_usable() {
+    let basic = DerivedBasic { a: 1, b: 2 };
+    let copy = basic;
+    assert_eq!(copy.a + copy.b, 3);
+    assert_eq!(DerivedHeader::LAYOUT[2].name, "checksum");
+}
+
+// Type alias and impl block tests
+#[assert_size(8)]
+type GenericU64 = GenericStruct<u64>;
+
+#[assert_size(4, align = 4)]
+type GenericU32 = GenericStruct<u32>;
+
+#[assert_size(target_pointer_width = "64" => 8, _ => 4)]
+type BorrowedHeader = &'static ReportHeader;
+
+#[assert_size(same_layout_as = u8)]
+type GenericU8 = GenericStruct<u8>;
+
+#[assert_size(same_as = &'static ReportHeader)]
+type BorrowedReport<'a> = &'a ReportHeader;
+
+#[assert_size(16)]
+impl GenericStruct<[u8; 16]> {
+    fn first(&self) -> u8 {
+        self.value[0]
+    }
+}
+
+struct AliasHandle<T>(T);
+
+#[assert_size(4, option_same_size)]
+impl From<u32> for AliasHandle<core::num::NonZeroU32> {
+    fn from(value: u32) -> Self {
+        AliasHandle(core::num::NonZeroU32::new(value).unwrap())
+    }
+}
+
+struct Borrowed<'a>(&'a [u8]);
+
+#[assert_size(same_as = &'static [u8])]
+impl<'a> From<&'a [u8]> for Borrowed<'a> {
+    fn from(bytes: &'a [u8]) -> Self {
+        Borrowed(bytes)
+    }
+}
+
+#[test]
+fn annotated_impl_blocks_are_kept() {
+    assert_eq!(GenericStruct { value: [7; 16] }.first(), 7);
+    assert_eq!(AliasHandle::from(3).0.get(), 3);
+}
+
+// Layout snapshot tests
+#[assert_size(16, test = "tests/layouts")]
+#[repr(C)]
+struct SnapshotHeader {
+    kind: u8,
+    length: u32,
+    checksum: u64,
+}
+
+#[assert_size(8, test = "tests/layouts", for = [SnapshotGeneric<u32>, SnapshotGeneric<u16> => 4])]
+#[repr(C)]
+struct SnapshotGeneric<T> {
+    value: T,
+    tail: u16,
+}
+
+#[assert_size(8, test = "tests/layouts")]
+union SnapshotUnion {
+    unsigned: u64,
+    bytes: [u8; 3],
+}
+
+#[assert_size(2, test = "tests/layouts")]
+#[repr(u8)]
+enum SnapshotEnum {
+    Byte(u8),
+    Empty,
+}
+
+#[derive(AssertSize)]
+#[size(8, test = "tests/layouts")]
+struct SnapshotTuple(u32, u16);
+
+// Lockfile snapshot tests, which only run on the targets recorded in `tests/layout.lock`
+#[cfg(all(target_arch = "x86_64", target_os = "linux", target_env = "gnu"))]
+#[assert_size(snapshot = "tests/layout.lock")]
+#[repr(C)]
+struct LockedHeader {
+    kind: u8,
+    length: u32,
+}
+
+#[cfg(all(target_arch = "x86_64", target_os = "linux", target_env = "gnu"))]
+#[assert_size(snapshot = "tests/layout.lock", for = [LockedGeneric<u64>, LockedGeneric<u16>])]
+struct LockedGeneric<T> {
+    value: T,
+}
+
+#[cfg(all(target_arch = "x86_64", target_os = "linux", target_env = "gnu"))]
+#[assert_size(<= 16, snapshot = "tests/layout.lock", option_same_size)]
+struct LockedHandle(core::num::NonZeroU64);
+
+#[cfg(all(target_arch = "x86_64", target_os = "linux", target_env = "gnu"))]
+mod locked {
+    use assert_size_derive::{AssertSize, assert_size};
+
+    // Same name as the type in the parent module, but recorded separately.
+    #[assert_size(snapshot = "tests/layout.lock")]
+    pub struct LockedHeader([u8; 3]);
+
+    #[derive(AssertSize)]
+    #[size(snapshot = "tests/layout.lock")]
+    pub struct LockedDerived(u16, u16, u16);
+}
+
+// Warning level tests
+#[assert_size(16, align = 8, option_same_size, no_padding, level = "warn")]
+#[repr(C)]
+struct WarnHeader {
+    #[assert_offset(0)]
+    kind: u64,
+    #[assert_offset(8)]
+    length: core::num::NonZeroU64,
+}
+
+#[assert_size(8..=16, level = "warn", for = [WarnWrapper<u64>, WarnWrapper<[u8; 16]> => <= 16])]
+struct WarnWrapper<T> {
+    value: T,
+}
+
+#[assert_size(4, level = "error")]
+struct ExplicitErrorLevel(u32);
+
+#[assert_align(8, level = "warn")]
+struct WarnAligned(u64);
+
+#[derive(AssertSize)]
+#[size(2, level = "warn")]
+#[repr(u8)]
+enum WarnDerived {
+    A(u8),
+    B(u8),
+}
+
+assert_sizes! {
+    u64 => (8, level = "warn"),
+    core::num::NonZeroU32 => (4, option_same_size, level = "warn"),
+}
@@ -0,0 +1,16 @@
+//! Checks the messages of failed assertions, which `compile_fail` doctests cannot, and that
+//! failed assertions with `level = "warn"` still compile.
+
+// With the `warn` feature, every failed assertion compiles.
+#[cfg(not(feature = "warn"))]
+#[test]
+fn ui() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+}
+
+#[test]
+fn warn_level() {
+    let cases = trybuild::TestCases::new();
+    cases.pass("tests/ui/pass/*.rs");
+}
@@ -0,0 +1,12 @@
+use assert_size_derive::assert_size;
+
+#[assert_size(<= 8)]
+struct Header {
+    kind: u32,
+    length: u64,
+}
+
+#[assert_size(4..8)]
+struct Word(u64);
+
+fn main() {}
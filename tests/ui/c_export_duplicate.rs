@@ -0,0 +1,24 @@
+use assert_size_derive::assert_size;
+
+mod v1 {
+    use super::*;
+
+    #[assert_size(4, c_export = "target/c_export/duplicate.h")]
+    #[repr(C)]
+    pub struct Header {
+        kind: u32,
+    }
+}
+
+mod v2 {
+    use super::*;
+
+    #[assert_size(8, c_export = "target/c_export/duplicate.h")]
+    #[repr(C)]
+    pub struct Header {
+        kind: u32,
+        length: u32,
+    }
+}
+
+fn main() {}
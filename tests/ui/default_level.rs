@@ -0,0 +1,13 @@
+// The same assertions as in `pass/warn_level.rs` fail without `level = "warn"`.
+
+use assert_size_derive::{assert_size, assert_sizes};
+
+#[assert_size(12, align = 4)]
+struct Header {
+    kind: u32,
+    length: u64,
+}
+
+assert_sizes!(u64 => 4);
+
+fn main() {}
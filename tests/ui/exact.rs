@@ -0,0 +1,9 @@
+use assert_size_derive::assert_size;
+
+#[assert_size(12)]
+struct Header {
+    kind: u32,
+    length: u64,
+}
+
+fn main() {}
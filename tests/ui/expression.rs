@@ -0,0 +1,11 @@
+use assert_size_derive::assert_size;
+
+const HEADER_LEN: usize = 4;
+
+#[assert_size(HEADER_LEN + 4)]
+struct Header {
+    kind: u32,
+    length: u64,
+}
+
+fn main() {}
@@ -0,0 +1,25 @@
+// Failed assertions with `level = "warn"` are only warnings, so this compiles.
+
+use assert_size_derive::{assert_size, assert_sizes};
+
+#[assert_size(12, align = 4, level = "warn")]
+struct Header {
+    kind: u32,
+    length: u64,
+}
+
+assert_sizes!(u64 => (4, level = "warn"));
+
+#[assert_size(8, repr = "C", level = "warn")]
+struct Unrepresented {
+    value: u64,
+}
+
+#[assert_size(1, discriminant = u16, level = "warn")]
+#[repr(u8)]
+enum Narrow {
+    First,
+    Second,
+}
+
+fn main() {}